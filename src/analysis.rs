@@ -0,0 +1,122 @@
+//! Estimate of how much of the historical revenue went to each fee share.
+//!
+//! Only the winners' prizes are recorded, so the other shares are derived
+//! from them: each prize is taken as `winner_fee_percent` of that draw's
+//! gross revenue, and the gross is split again by the stored percentages.
+
+use crate::new_rl::NewRL;
+use crate::old_rl::OldRL;
+
+/// Fee percentages the shares are derived with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeSplit {
+    pub team: u8,
+    pub distribution: u8,
+    pub winner: u8,
+    pub burn: u8,
+}
+
+impl From<&OldRL> for FeeSplit {
+    fn from(old: &OldRL) -> Self {
+        Self {
+            team: old.team_fee_percent,
+            distribution: old.distribution_fee_percent,
+            winner: old.winner_fee_percent,
+            burn: old.burn_percent,
+        }
+    }
+}
+
+impl From<&NewRL> for FeeSplit {
+    fn from(new: &NewRL) -> Self {
+        Self {
+            team: new.team_fee_percent,
+            distribution: new.distribution_fee_percent,
+            winner: new.winner_fee_percent,
+            burn: new.burn_percent,
+        }
+    }
+}
+
+/// Totals implied by the recorded prizes, in the smallest currency unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FeeTotals {
+    /// Number of prizes the totals are derived from.
+    pub winners: usize,
+    /// Sum of the recorded prizes.
+    pub paid_to_winners: u128,
+    /// Implied gross revenue of the draws.
+    pub gross: u128,
+    pub team: u128,
+    pub distribution: u128,
+    pub burn: u128,
+}
+
+impl FeeSplit {
+    /// Back-computes the gross revenue and the team, distribution and burn
+    /// shares of every prize in `revenues` and sums them up.
+    ///
+    /// Each draw is rounded down on its own, as the contract pays whole
+    /// units. Returns `None` when `winner` is 0: no prize then carries any
+    /// information about the gross.
+    pub fn implied_totals(&self, revenues: impl IntoIterator<Item = u64>) -> Option<FeeTotals> {
+        if self.winner == 0 {
+            return None;
+        }
+        let share = |gross: u128, percent: u8| gross * u128::from(percent) / 100;
+        let mut totals = FeeTotals::default();
+        for revenue in revenues {
+            let gross = u128::from(revenue) * 100 / u128::from(self.winner);
+            totals.winners += 1;
+            totals.paid_to_winners += u128::from(revenue);
+            totals.gross += gross;
+            totals.team += share(gross, self.team);
+            totals.distribution += share(gross, self.distribution);
+            totals.burn += share(gross, self.burn);
+        }
+        Some(totals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPLIT: FeeSplit = FeeSplit {
+        team: 10,
+        distribution: 20,
+        winner: 50,
+        burn: 20,
+    };
+
+    #[test]
+    fn single_prize_implies_the_gross_and_shares() {
+        assert_eq!(
+            SPLIT.implied_totals([5_000]),
+            Some(FeeTotals {
+                winners: 1,
+                paid_to_winners: 5_000,
+                gross: 10_000,
+                team: 1_000,
+                distribution: 2_000,
+                burn: 2_000,
+            })
+        );
+    }
+
+    #[test]
+    fn each_draw_is_rounded_down_on_its_own() {
+        let totals = SPLIT.implied_totals([3, 3]).unwrap();
+        // 3 * 100 / 50 = 6 per draw, 6 * 10 / 100 rounds to 0 each time
+        assert_eq!(totals.gross, 12);
+        assert_eq!(totals.team, 0);
+        assert_eq!(totals.distribution, 2);
+    }
+
+    #[test]
+    fn zero_winner_fee_yields_no_totals() {
+        let split = FeeSplit { winner: 0, ..SPLIT };
+        assert_eq!(split.implied_totals([5_000]), None);
+    }
+}
@@ -0,0 +1,255 @@
+//! Every consistency check of a state in one pass, grouped by the part of
+//! the state it looks at (the `validate` command).
+//!
+//! The conversion runs the same validators but stops at the first hard
+//! error; here each check is run on its own and its outcome recorded.
+
+use crate::new_rl::NewRL;
+use crate::old_rl::{OldRL, PlayerHashSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+/// Part of the state a check looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckCategory {
+    Fees,
+    Schedule,
+    State,
+    Players,
+    Winners,
+}
+
+impl CheckCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckCategory::Fees => "Fees",
+            CheckCategory::Schedule => "Schedule",
+            CheckCategory::State => "State",
+            CheckCategory::Players => "Players",
+            CheckCategory::Winners => "Winners",
+        }
+    }
+}
+
+/// Outcome of one check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub category: CheckCategory,
+    pub name: &'static str,
+    /// Why the check failed; `None` when it passed.
+    pub failure: Option<String>,
+}
+
+impl Check {
+    fn new<E: Display>(category: CheckCategory, name: &'static str, result: Result<(), E>) -> Self {
+        Self {
+            category,
+            name,
+            failure: result.err().map(|e| e.to_string()),
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Runs every check that applies to a NewRL state, in category order.
+pub fn check_new(new_rl: &NewRL) -> Vec<Check> {
+    let mut checks = Vec::new();
+    checks.push(Check::new(
+        CheckCategory::Fees,
+        "fee split",
+        new_rl.validate_fees(),
+    ));
+    checks.push(Check::new(
+        CheckCategory::Schedule,
+        "draw hour and weekdays",
+        new_rl.validate_schedule(),
+    ));
+    push_shared(&mut checks, new_rl);
+
+    let active = new_rl.active_player_count();
+    checks.push(Check::new(
+        CheckCategory::Players,
+        "player counter",
+        if new_rl.player_counter == active as u64 {
+            Ok(())
+        } else {
+            Err(format!(
+                "player_counter is {} but {} player slot(s) are populated",
+                new_rl.player_counter, active
+            ))
+        },
+    ));
+    // The flat array has no hash set of its own, but the duplicate search is the same
+    checks.push(duplicates_check(&PlayerHashSet::from_players(
+        &new_rl.players,
+    )));
+    checks.sort_by_key(|check| check.category);
+    checks
+}
+
+/// Runs every check that applies to an OldRL state, in category order.
+///
+/// Fee, state and winner checks run on the converted state; the schedule
+/// and `player_counter` do not exist in the old layout, and the hash set
+/// is checked as stored.
+pub fn check_old(old: &OldRL) -> Vec<Check> {
+    let new_rl = NewRL::from_old(old);
+    let mut checks = Vec::new();
+    checks.push(Check::new(
+        CheckCategory::Fees,
+        "fee split",
+        new_rl.validate_fees(),
+    ));
+    push_shared(&mut checks, &new_rl);
+    checks.push(Check::new(
+        CheckCategory::Players,
+        "hash set integrity",
+        old.players.verify(),
+    ));
+    checks.push(duplicates_check(&old.players));
+    checks.sort_by_key(|check| check.category);
+    checks
+}
+
+/// State and winner checks that read the same in both layouts.
+fn push_shared(checks: &mut Vec<Check>, new_rl: &NewRL) {
+    checks.push(Check::new(
+        CheckCategory::State,
+        "state and ticket price",
+        new_rl.validate_state(),
+    ));
+    checks.push(Check::new(
+        CheckCategory::State,
+        "owner and team addresses",
+        new_rl.validate_addresses(),
+    ));
+    let zero = new_rl.zero_addresses_while_selling();
+    checks.push(Check::new(
+        CheckCategory::State,
+        "addresses set while selling",
+        if zero.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "contract is Selling but {} is the zero address",
+                zero.join(" and ")
+            ))
+        },
+    ));
+
+    let inconsistent = new_rl.inconsistent_winner_slots();
+    checks.push(Check::new(
+        CheckCategory::Winners,
+        "winner address and revenue",
+        if inconsistent.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "slot(s) {:?} have an address without revenue or revenue without an address",
+                inconsistent
+            ))
+        },
+    ));
+    let out_of_order = new_rl.winners_out_of_order();
+    checks.push(Check::new(
+        CheckCategory::Winners,
+        "winners in draw order",
+        if out_of_order.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "slot(s) {:?} are older than the entry before them in ring order",
+                out_of_order
+            ))
+        },
+    ));
+    checks.push(Check::new(
+        CheckCategory::Winners,
+        "winners counter",
+        new_rl.validate_winners_counter(),
+    ));
+}
+
+fn duplicates_check(players: &PlayerHashSet) -> Check {
+    let duplicates = players.find_duplicates();
+    Check::new(
+        CheckCategory::Players,
+        "duplicate players",
+        if duplicates.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} player(s) occupy more than one slot",
+                duplicates.len()
+            ))
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{EState, Id};
+    use crate::new_rl::WinnerInfoNew;
+
+    fn id(byte: u8) -> Id {
+        Id { data: [byte; 32] }
+    }
+
+    /// A selling state that passes every check.
+    fn healthy() -> NewRL {
+        let mut new_rl = NewRL::builder()
+            .team_address(id(0xAA))
+            .owner_address(id(0xBB))
+            .team_fee_percent(10)
+            .distribution_fee_percent(20)
+            .winner_fee_percent(50)
+            .burn_percent(20)
+            .ticket_price(1_000_000)
+            .schedule(0b0000_1001)
+            .draw_hour(12)
+            .current_state(EState::Selling)
+            .build()
+            .unwrap();
+        new_rl.players[0] = id(1);
+        new_rl.player_counter = 1;
+        new_rl.record_winner(WinnerInfoNew::new(id(2), 5_000, 1_000, 150, 0));
+        new_rl
+    }
+
+    fn failures(checks: &[Check]) -> Vec<&'static str> {
+        checks
+            .iter()
+            .filter(|check| !check.passed())
+            .map(|check| check.name)
+            .collect()
+    }
+
+    #[test]
+    fn healthy_state_passes_every_check() {
+        let checks = check_new(&healthy());
+        assert_eq!(failures(&checks), Vec::<&str>::new());
+        assert!(checks.is_sorted_by_key(|check| check.category));
+    }
+
+    #[test]
+    fn fixture_fails_exactly_two_checks() {
+        let mut new_rl = healthy();
+        new_rl.burn_percent = 10;
+        new_rl.owner_address = new_rl.team_address;
+
+        let checks = check_new(&new_rl);
+        assert_eq!(failures(&checks), ["fee split", "owner and team addresses"]);
+        let fees = checks
+            .iter()
+            .find(|check| check.name == "fee split")
+            .unwrap();
+        assert_eq!(fees.category, CheckCategory::Fees);
+        assert!(fees.failure.as_ref().unwrap().contains("90"));
+    }
+}
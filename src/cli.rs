@@ -0,0 +1,1180 @@
+//! Command line parsing and usage text for the `rlconverter` binary.
+
+use rlconverter::common::{day_of_week, DisplayConfig, EState, Id, DEFAULT_DISPLAY_WIDTH};
+#[cfg(feature = "serde")]
+use rlconverter::config::MigrationConfig;
+use rlconverter::diff::FIELD_NAMES;
+use rlconverter::new_rl::{NewFieldOverrides, WinnerInfoNew, SCHEDULE_MASK};
+use rlconverter::StateFormat;
+use std::path::{Path, PathBuf};
+
+/// Default of `--preview-bytes`
+pub const DEFAULT_PREVIEW_BYTES: usize = 32;
+
+/// Output encoding selected with `--format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Raw NewRL memory image, loadable by the contract
+    Binary,
+    /// Pretty-printed JSON with zero slots omitted
+    #[cfg(feature = "serde")]
+    Json,
+    /// Single-line JSON followed by a newline, for NDJSON logs (`--json-compact`)
+    #[cfg(feature = "serde")]
+    JsonCompact,
+    /// Binary image as one base64 line on stdout (`--stdout-base64`)
+    Base64,
+    /// Scalars plus only the active players and winners (`--compact-binary`)
+    CompactBinary,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "binary" => Ok(OutputFormat::Binary),
+            #[cfg(feature = "serde")]
+            "json" => Ok(OutputFormat::Json),
+            #[cfg(not(feature = "serde"))]
+            "json" => Err("JSON output requires building with the `serde` feature".to_string()),
+            other => Err(format!(
+                "unknown format '{}' (expected 'binary' or 'json')",
+                other
+            )),
+        }
+    }
+}
+
+/// How the input file holds the state image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEncoding {
+    /// Raw bytes, optionally gzip-compressed
+    Binary,
+    /// Standard base64 text (`--input-base64`)
+    Base64,
+    /// Hex digits, optionally `0x`-prefixed and spaced (`--input-hex`)
+    Hex,
+}
+
+/// When the dumps are colored, selected with `--color`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color when the dumps go to a terminal and `NO_COLOR` is not set
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            other => Err(format!(
+                "unknown color mode '{}' (expected auto, always or never)",
+                other
+            )),
+        }
+    }
+}
+
+/// Parsed command line options
+pub struct Options {
+    /// State file, or a directory of state files for batch conversion
+    pub input: String,
+    /// Output file; `None` in batch mode
+    pub output_file: Option<String>,
+    /// Batch mode only: directory receiving the converted files
+    pub output_dir: Option<String>,
+    /// Batch mode only: output path pattern with `{dir}`, `{stem}` and `{ext}`
+    pub output_template: Option<String>,
+    pub format: OutputFormat,
+    pub dry_run: bool,
+    /// Accept OldRL inputs followed by zero padding
+    pub allow_trailing: bool,
+    /// Drop source players that do not fit into the NewRL players array
+    /// instead of failing
+    pub truncate: bool,
+    /// Reorder a winners history that is not in draw order
+    pub sort_winners: bool,
+    /// Treat semantic state warnings as errors
+    pub strict: bool,
+    /// Pass inputs that are already NewRL states through instead of failing,
+    /// allow the output path to be the input path, and overwrite an existing
+    /// output without asking
+    pub force: bool,
+    /// Values for new-only fields given on the command line
+    pub overrides: NewFieldOverrides,
+    /// Per-file values for new-only fields loaded from `--config`
+    #[cfg(feature = "serde")]
+    pub config: Option<MigrationConfig>,
+    /// Where to write the JSON conversion report
+    pub report: Option<String>,
+    /// Separator width of the state dumps
+    pub width: usize,
+    /// Gzip-compress the written output
+    pub gzip_out: bool,
+    /// JSON file collecting input/output digests of every written file
+    pub manifest: Option<String>,
+    /// Print nothing but errors
+    pub quiet: bool,
+    /// Text encoding of the input files, if any
+    pub input_encoding: InputEncoding,
+    /// Break the size change down by region after each conversion
+    pub explain_layout: bool,
+    /// Replace addresses in the dumps with pseudonyms
+    pub redact: bool,
+    /// Number of leading input and output bytes shown in the debug log
+    pub preview_bytes: usize,
+    /// `current_state` forced onto the converted state
+    pub state: Option<EState>,
+    /// Append a checksum trailer to binary output
+    pub with_checksum: bool,
+    /// Players and winners listed per dump; `None` lists all
+    pub limit: Option<usize>,
+    pub color: ColorChoice,
+}
+
+impl Options {
+    /// Rendering of the OldRL/NewRL dumps
+    pub fn display_config(&self) -> DisplayConfig {
+        DisplayConfig {
+            width: self.width,
+            redact: self.redact,
+            limit: self.limit,
+            color: self.use_color(),
+        }
+    }
+
+    /// Resolves `--color auto`: the dumps move to stderr while stdout
+    /// carries the state, so that is the stream checked for a terminal
+    fn use_color(&self) -> bool {
+        use std::io::IsTerminal;
+
+        match self.color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+                let terminal = if self.output_file.as_deref() == Some("-") {
+                    std::io::stderr().is_terminal()
+                } else {
+                    std::io::stdout().is_terminal()
+                };
+                terminal && !no_color
+            }
+        }
+    }
+
+    /// New-only field values for `input`: command line flags take precedence
+    /// over its `--config` entry
+    #[cfg_attr(not(feature = "serde"), allow(unused_variables))]
+    pub fn overrides_for(&self, input: &Path) -> NewFieldOverrides {
+        #[cfg(feature = "serde")]
+        if let Some(config) = &self.config {
+            return self.overrides.or(config.overrides_for(input));
+        }
+        self.overrides
+    }
+}
+
+/// Options of the `diff` command
+pub struct DiffOptions {
+    pub left: String,
+    pub right: String,
+    /// Also print fields that are identical
+    pub verbose: bool,
+    /// Compare only these fields (`--field`, repeatable); empty compares all
+    pub fields: Vec<String>,
+}
+
+/// Options of the `merge` command
+pub struct MergeOptions {
+    /// NewRL file every field except the winners history is taken from
+    pub first: String,
+    pub second: String,
+    pub output: String,
+}
+
+/// Options of the `players` command
+pub struct PlayersOptions {
+    pub file: String,
+    /// Print raw base64 ids instead of Qubic identities
+    pub base64: bool,
+}
+
+/// Options of the `winners-csv` command
+pub struct WinnersCsvOptions {
+    pub file: String,
+    /// Inclusive epoch range from `--epochs`
+    pub epochs: Option<(u16, u16)>,
+}
+
+/// Options of the `winners-for` command
+pub struct WinnersForOptions {
+    pub file: String,
+    /// Winner to look up, given as an identity or base64
+    pub address: Id,
+}
+
+/// Default of `schedule-preview --count`
+pub const DEFAULT_PREVIEW_DRAWS: usize = 5;
+
+/// Options of the `schedule-preview` command
+pub struct SchedulePreviewOptions {
+    pub file: String,
+    /// Number of draws to list
+    pub count: usize,
+    /// Start of the preview; `None` is now
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    /// A known second Wednesday, deciding the Two-Wednesdays rule
+    pub second_wednesday: Option<chrono::NaiveDate>,
+}
+
+/// Options of the `simulate-rollover` command
+pub struct RolloverOptions {
+    pub file: String,
+    pub output: String,
+    /// Winner to record in the history, from `--winner`, `--revenue`,
+    /// `--epoch`, `--tick` and `--drawn-at`
+    pub winner: Option<WinnerInfoNew>,
+}
+
+/// Options of the hidden `gen` command
+#[cfg(feature = "gen")]
+pub struct GenOptions {
+    pub output: String,
+    /// Filled player slots
+    pub players: usize,
+    /// Recorded draws; more than the history holds wraps it
+    pub winners: usize,
+    /// Seed of the generator, so fixtures can be reproduced
+    pub seed: u64,
+    /// Weekday bitmask of the state and of the winners' draw days
+    pub schedule: u8,
+}
+
+/// Action selected on the command line
+pub enum Command {
+    /// Convert an OldRL file (or directory of files) to NewRL
+    Convert(Box<Options>),
+    /// Compare two NewRL files field by field
+    Diff(DiffOptions),
+    /// Check that an OldRL file re-encodes to identical bytes
+    Verify(String),
+    /// Print the active players of an OldRL or NewRL file, one per line
+    Players(PlayersOptions),
+    /// Print the winners history of an OldRL or NewRL file as CSV
+    WinnersCsv(WinnersCsvOptions),
+    /// Print a short numeric summary of an OldRL or NewRL file
+    Stats(String),
+    /// Run every consistency check on an OldRL or NewRL file
+    Validate(String),
+    /// Estimate the fee shares behind the recorded prizes of a file
+    Analyze(String),
+    /// Print the winnings of one address recorded in an OldRL or NewRL file
+    WinnersFor(WinnersForOptions),
+    /// Combine the winners histories of two NewRL files into a third
+    Merge(MergeOptions),
+    /// Print the field offsets of the given layouts
+    Layout(Vec<StateFormat>),
+    /// List the next draw times of a NewRL file
+    SchedulePreview(SchedulePreviewOptions),
+    /// Write a NewRL file as it would look after the next epoch boundary
+    SimulateRollover(RolloverOptions),
+    /// Print the usage text, exit codes included
+    Help,
+    /// Write a random NewRL state for testing
+    #[cfg(feature = "gen")]
+    Gen(GenOptions),
+}
+
+/// Parses positional arguments and flags (everything after the program name)
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    match args.first().map(String::as_str) {
+        Some("-h" | "--help") => Ok(Command::Help),
+        Some("diff") => parse_diff_args(&args[1..]).map(Command::Diff),
+        Some("verify") => parse_verify_args(&args[1..]).map(Command::Verify),
+        Some("players") => parse_players_args(&args[1..]).map(Command::Players),
+        Some("winners-csv") => parse_winners_csv_args(&args[1..]).map(Command::WinnersCsv),
+        Some("stats") => match &args[1..] {
+            [flag] if flag.starts_with("--") => Err(format!("unknown option '{}'", flag)),
+            [file] => Ok(Command::Stats(file.clone())),
+            _ => Err("stats expects exactly one state file".to_string()),
+        },
+        Some("validate") => match &args[1..] {
+            [flag] if flag.starts_with("--") => Err(format!("unknown option '{}'", flag)),
+            [file] => Ok(Command::Validate(file.clone())),
+            _ => Err("validate expects exactly one state file".to_string()),
+        },
+        Some("analyze") => match &args[1..] {
+            [flag] if flag.starts_with("--") => Err(format!("unknown option '{}'", flag)),
+            [file] => Ok(Command::Analyze(file.clone())),
+            _ => Err("analyze expects exactly one state file".to_string()),
+        },
+        Some("winners-for") => parse_winners_for_args(&args[1..]).map(Command::WinnersFor),
+        Some("merge") => parse_merge_args(&args[1..]).map(Command::Merge),
+        Some("layout") => parse_layout_args(&args[1..]).map(Command::Layout),
+        Some("schedule-preview") => {
+            parse_schedule_preview_args(&args[1..]).map(Command::SchedulePreview)
+        }
+        Some("simulate-rollover") => parse_rollover_args(&args[1..]).map(Command::SimulateRollover),
+        #[cfg(feature = "gen")]
+        Some("gen") => parse_gen_args(&args[1..]).map(Command::Gen),
+        _ => parse_convert_args(args).map(|options| Command::Convert(Box::new(options))),
+    }
+}
+
+/// `layout [old|extended-old|new]`; without an argument the old and new layouts
+fn parse_layout_args(args: &[String]) -> Result<Vec<StateFormat>, String> {
+    match args {
+        [] => Ok(vec![StateFormat::Old, StateFormat::New]),
+        [layout] => match layout.as_str() {
+            "old" => Ok(vec![StateFormat::Old]),
+            "extended-old" => Ok(vec![StateFormat::ExtendedOld]),
+            "new" => Ok(vec![StateFormat::New]),
+            other => Err(format!(
+                "unknown layout '{}' (expected old, extended-old or new)",
+                other
+            )),
+        },
+        _ => Err("layout expects at most one layout name".to_string()),
+    }
+}
+
+fn parse_diff_args(args: &[String]) -> Result<DiffOptions, String> {
+    let mut positional = Vec::new();
+    let mut verbose = false;
+    let mut fields = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--verbose" => verbose = true,
+            "--field" => {
+                let name = iter
+                    .next()
+                    .ok_or_else(|| "--field requires a field name".to_string())?;
+                if !FIELD_NAMES.contains(&name.as_str()) {
+                    return Err(format!(
+                        "unknown field '{}' (valid fields: {})",
+                        name,
+                        FIELD_NAMES.join(", ")
+                    ));
+                }
+                fields.push(name.clone());
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option '{}'", flag)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    if positional.len() != 2 {
+        return Err("diff expects exactly two NewRL files".to_string());
+    }
+    let right = positional.pop().unwrap();
+    let left = positional.pop().unwrap();
+
+    Ok(DiffOptions {
+        left,
+        right,
+        verbose,
+        fields,
+    })
+}
+
+fn parse_merge_args(args: &[String]) -> Result<MergeOptions, String> {
+    if let Some(flag) = args.iter().find(|arg| arg.starts_with("--")) {
+        return Err(format!("unknown option '{}'", flag));
+    }
+    match args {
+        [first, second, output] => Ok(MergeOptions {
+            first: first.clone(),
+            second: second.clone(),
+            output: output.clone(),
+        }),
+        _ => Err("merge expects two NewRL files and an output file".to_string()),
+    }
+}
+
+/// `gen [--players <n>] [--winners <n>] [--seed <n>] [--schedule <mask>] <output>`
+#[cfg(feature = "gen")]
+fn parse_gen_args(args: &[String]) -> Result<GenOptions, String> {
+    use rlconverter::common::RL_MAX_NUMBER_OF_PLAYERS;
+
+    let mut positional = Vec::new();
+    let mut players = 0;
+    let mut winners = 0;
+    let mut seed = 0;
+    let mut schedule = SCHEDULE_MASK;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--players" => {
+                let value = iter.next().ok_or("--players requires a value")?;
+                players = parse_int("--players", value)?;
+                if players > RL_MAX_NUMBER_OF_PLAYERS {
+                    return Err(format!(
+                        "--players {} exceeds the {} player slots",
+                        players, RL_MAX_NUMBER_OF_PLAYERS
+                    ));
+                }
+            }
+            "--winners" => {
+                let value = iter.next().ok_or("--winners requires a value")?;
+                winners = parse_int("--winners", value)?;
+            }
+            "--seed" => {
+                let value = iter.next().ok_or("--seed requires a value")?;
+                seed = parse_int("--seed", value)?;
+            }
+            "--schedule" => {
+                let value = iter.next().ok_or("--schedule requires a value")?;
+                schedule = parse_schedule("--schedule", value)?;
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option '{}'", flag)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    match positional.pop() {
+        Some(output) if positional.is_empty() => Ok(GenOptions {
+            output,
+            players,
+            winners,
+            seed,
+            schedule,
+        }),
+        _ => Err("gen expects exactly one output file".to_string()),
+    }
+}
+
+fn parse_schedule_preview_args(args: &[String]) -> Result<SchedulePreviewOptions, String> {
+    let mut positional = Vec::new();
+    let mut count = DEFAULT_PREVIEW_DRAWS;
+    let mut from = None;
+    let mut second_wednesday = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--count" => {
+                let value = iter.next().ok_or("--count requires a value")?;
+                count = parse_int("--count", value)?;
+            }
+            "--from" => {
+                let value = iter.next().ok_or("--from requires a value")?;
+                let parsed = chrono::DateTime::parse_from_rfc3339(value).map_err(|e| {
+                    format!(
+                        "--from '{}' is not an RFC 3339 time like 2025-01-01T12:00:00Z: {}",
+                        value, e
+                    )
+                })?;
+                from = Some(parsed.with_timezone(&chrono::Utc));
+            }
+            "--second-wednesday" => {
+                let value = iter.next().ok_or("--second-wednesday requires a value")?;
+                let date: chrono::NaiveDate = value.parse().map_err(|e| {
+                    format!(
+                        "--second-wednesday '{}' is not a date like 2025-01-08: {}",
+                        value, e
+                    )
+                })?;
+                if chrono::Datelike::weekday(&date) != chrono::Weekday::Wed {
+                    return Err(format!("--second-wednesday {} is not a Wednesday", date));
+                }
+                second_wednesday = Some(date);
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option '{}'", flag)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    match positional.pop() {
+        Some(file) if positional.is_empty() => Ok(SchedulePreviewOptions {
+            file,
+            count,
+            from,
+            second_wednesday,
+        }),
+        _ => Err("schedule-preview expects exactly one NewRL file".to_string()),
+    }
+}
+
+fn parse_verify_args(args: &[String]) -> Result<String, String> {
+    match args {
+        [flag] if flag.starts_with("--") => Err(format!("unknown option '{}'", flag)),
+        [file] => Ok(file.clone()),
+        _ => Err("verify expects exactly one OldRL file".to_string()),
+    }
+}
+
+fn parse_players_args(args: &[String]) -> Result<PlayersOptions, String> {
+    let mut positional = Vec::new();
+    let mut base64 = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "--base64" => base64 = true,
+            flag if flag.starts_with("--") => return Err(format!("unknown option '{}'", flag)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    match positional.pop() {
+        Some(file) if positional.is_empty() => Ok(PlayersOptions { file, base64 }),
+        _ => Err("players expects exactly one state file".to_string()),
+    }
+}
+
+fn parse_winners_csv_args(args: &[String]) -> Result<WinnersCsvOptions, String> {
+    let mut positional = Vec::new();
+    let mut epochs = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--epochs" => {
+                let value = iter.next().ok_or("--epochs requires a value")?;
+                epochs = Some(parse_epoch_range(value)?);
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option '{}'", flag)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    match positional.pop() {
+        Some(file) if positional.is_empty() => Ok(WinnersCsvOptions { file, epochs }),
+        _ => Err("winners-csv expects exactly one state file".to_string()),
+    }
+}
+
+/// Parses an inclusive `<start>..<end>` epoch range
+fn parse_epoch_range(value: &str) -> Result<(u16, u16), String> {
+    let (start, end) = value
+        .split_once("..")
+        .ok_or_else(|| format!("--epochs '{}' must look like <start>..<end>", value))?;
+    Ok((parse_int("--epochs", start)?, parse_int("--epochs", end)?))
+}
+
+/// `simulate-rollover <file> <out>`; a winner needs all of `--winner`,
+/// `--revenue`, `--epoch` and `--tick`, its weekday is taken from
+/// `--drawn-at` (default: now)
+fn parse_rollover_args(args: &[String]) -> Result<RolloverOptions, String> {
+    let mut positional = Vec::new();
+    let mut winner_address: Option<Id> = None;
+    let mut revenue = None;
+    let mut epoch = None;
+    let mut tick = None;
+    let mut drawn_at = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--winner" => {
+                let value = iter.next().ok_or("--winner requires an address")?;
+                let id: Id = value
+                    .parse()
+                    .map_err(|e| format!("invalid address '{}': {}", value, e))?;
+                if id.is_zero() {
+                    return Err("--winner cannot be the zero address".to_string());
+                }
+                winner_address = Some(id);
+            }
+            "--revenue" => {
+                let value = iter.next().ok_or("--revenue requires a value")?;
+                let units: u64 = parse_int("--revenue", value)?;
+                // Every draw pays out, a zero prize would read as a corrupt slot
+                if units == 0 {
+                    return Err("--revenue must be at least 1".to_string());
+                }
+                revenue = Some(units);
+            }
+            "--epoch" => {
+                let value = iter.next().ok_or("--epoch requires a value")?;
+                epoch = Some(parse_int("--epoch", value)?);
+            }
+            "--tick" => {
+                let value = iter.next().ok_or("--tick requires a value")?;
+                tick = Some(parse_int("--tick", value)?);
+            }
+            "--drawn-at" => {
+                let value = iter.next().ok_or("--drawn-at requires a value")?;
+                let parsed = chrono::DateTime::parse_from_rfc3339(value).map_err(|e| {
+                    format!(
+                        "--drawn-at '{}' is not an RFC 3339 time like 2025-01-01T12:00:00Z: {}",
+                        value, e
+                    )
+                })?;
+                drawn_at = Some(parsed.with_timezone(&chrono::Utc));
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option '{}'", flag)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let winner = match (winner_address, revenue, epoch, tick) {
+        (Some(winner_address), Some(revenue), Some(epoch), Some(tick)) => {
+            let drawn_at = drawn_at.unwrap_or_else(chrono::Utc::now);
+            let day = day_of_week(drawn_at.timestamp().max(0) as u64);
+            Some(WinnerInfoNew::new(
+                winner_address,
+                revenue,
+                tick,
+                epoch,
+                day,
+            ))
+        }
+        (None, None, None, None) if drawn_at.is_some() => {
+            return Err("--drawn-at requires --winner".to_string())
+        }
+        (None, None, None, None) => None,
+        _ => {
+            return Err(
+                "--winner, --revenue, --epoch and --tick must be given together".to_string(),
+            )
+        }
+    };
+
+    match positional.as_slice() {
+        [file, output] => Ok(RolloverOptions {
+            file: file.clone(),
+            output: output.clone(),
+            winner,
+        }),
+        _ => Err("simulate-rollover expects a NewRL file and an output file".to_string()),
+    }
+}
+
+fn parse_winners_for_args(args: &[String]) -> Result<WinnersForOptions, String> {
+    if let Some(flag) = args.iter().find(|arg| arg.starts_with("--")) {
+        return Err(format!("unknown option '{}'", flag));
+    }
+    match args {
+        [file, address] => Ok(WinnersForOptions {
+            file: file.clone(),
+            address: address
+                .parse()
+                .map_err(|e| format!("invalid address '{}': {}", address, e))?,
+        }),
+        _ => Err("winners-for expects a state file and an address".to_string()),
+    }
+}
+
+/// Expands an `--output-template` for one batch input: `{dir}` is the
+/// input's directory, `{stem}` its file name without extension and `{ext}`
+/// the extension without the dot (empty when there is none)
+pub fn expand_output_template(template: &str, input: &Path) -> Result<PathBuf, String> {
+    let dir = input.parent().unwrap_or(Path::new(""));
+    let dir = if dir.as_os_str().is_empty() {
+        ".".into()
+    } else {
+        dir.to_string_lossy()
+    };
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = input.extension().unwrap_or_default().to_string_lossy();
+
+    let mut expanded = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("unclosed '{{' in --output-template '{}'", template))?;
+        match &rest[start + 1..start + end] {
+            "dir" => expanded.push_str(&dir),
+            "stem" => expanded.push_str(&stem),
+            "ext" => expanded.push_str(&ext),
+            other => {
+                return Err(format!(
+                    "unknown placeholder '{{{}}}' in --output-template (use {{dir}}, {{stem}} or {{ext}})",
+                    other
+                ))
+            }
+        }
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(PathBuf::from(expanded))
+}
+
+/// Parses an integer given in decimal, `0b` binary or `0x` hex notation
+fn parse_int<T: TryFrom<u64>>(flag: &str, value: &str) -> Result<T, String> {
+    let parsed = if let Some(bits) = value.strip_prefix("0b") {
+        u64::from_str_radix(bits, 2)
+    } else if let Some(hex) = value.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16)
+    } else {
+        value.parse()
+    };
+    parsed
+        .ok()
+        .and_then(|v| T::try_from(v).ok())
+        .ok_or_else(|| format!("invalid value '{}' for {}", value, flag))
+}
+
+/// Parses a weekday bitmask, rejecting bits above bit 6
+fn parse_schedule(flag: &str, value: &str) -> Result<u8, String> {
+    let schedule: u8 = parse_int(flag, value)?;
+    if schedule & !SCHEDULE_MASK != 0 {
+        return Err(format!(
+            "{} 0b{:08b} sets bits above bit 6 (bit 0 = Wednesday ... bit 6 = Tuesday)",
+            flag, schedule
+        ));
+    }
+    Ok(schedule)
+}
+
+/// Parses the arguments of the default conversion command
+fn parse_convert_args(args: &[String]) -> Result<Options, String> {
+    let mut positional = Vec::new();
+    let mut format = OutputFormat::Binary;
+    let mut dry_run = false;
+    let mut force = false;
+    let mut strict = false;
+    let mut allow_trailing = false;
+    let mut truncate = false;
+    let mut sort_winners = false;
+    let mut stdout_base64 = false;
+    let mut output_dir = None;
+    let mut output_template = None;
+    let mut overrides = NewFieldOverrides::default();
+    let mut report = None;
+    #[cfg(feature = "serde")]
+    let mut config = None;
+    let mut width = DEFAULT_DISPLAY_WIDTH;
+    let mut manifest = None;
+    let mut gzip_out = false;
+    let mut quiet = false;
+    let mut input_encoding = InputEncoding::Binary;
+    let mut explain_layout = false;
+    let mut redact = false;
+    let mut preview_bytes = DEFAULT_PREVIEW_BYTES;
+    let mut state = None;
+    let mut with_checksum = false;
+    let mut compact_binary = false;
+    let mut limit = None;
+    let mut color = ColorChoice::Auto;
+    #[cfg(feature = "serde")]
+    let mut json_compact = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                let value = iter.next().ok_or("--format requires a value")?;
+                format = value.parse()?;
+            }
+            "--dry-run" => dry_run = true,
+            "--force" => force = true,
+            "--strict" => strict = true,
+            "--allow-trailing" => allow_trailing = true,
+            "--truncate" => truncate = true,
+            "--sort-winners" => sort_winners = true,
+            "--stdout-base64" => stdout_base64 = true,
+            "--input-base64" | "--input-hex" => {
+                if input_encoding != InputEncoding::Binary {
+                    return Err("--input-base64 and --input-hex cannot be combined".to_string());
+                }
+                input_encoding = if arg == "--input-hex" {
+                    InputEncoding::Hex
+                } else {
+                    InputEncoding::Base64
+                };
+            }
+            "--explain-layout" => explain_layout = true,
+            "--redact" => redact = true,
+            #[cfg(feature = "serde")]
+            "--json-compact" => json_compact = true,
+            #[cfg(not(feature = "serde"))]
+            "--json-compact" => {
+                return Err("--json-compact requires building with the `serde` feature".to_string())
+            }
+            "--gzip-out" => gzip_out = true,
+            "--quiet" | "-q" => quiet = true,
+            "--output-dir" => {
+                let value = iter.next().ok_or("--output-dir requires a value")?;
+                output_dir = Some(value.clone());
+            }
+            "--output-template" => {
+                let value = iter.next().ok_or("--output-template requires a value")?;
+                // Expanding once catches unknown placeholders before any file is read
+                expand_output_template(value, Path::new("contract.185"))?;
+                output_template = Some(value.clone());
+            }
+            "--config" => {
+                let value = iter.next().ok_or("--config requires a value")?;
+                #[cfg(feature = "serde")]
+                {
+                    let text = std::fs::read_to_string(value)
+                        .map_err(|e| format!("cannot read --config '{}': {}", value, e))?;
+                    config = Some(
+                        MigrationConfig::from_toml(&text)
+                            .map_err(|e| format!("--config '{}': {}", value, e))?,
+                    );
+                }
+                #[cfg(not(feature = "serde"))]
+                return Err(format!(
+                    "--config '{}' requires building with the `serde` feature",
+                    value
+                ));
+            }
+            "--report" => {
+                let value = iter.next().ok_or("--report requires a value")?;
+                if cfg!(not(feature = "serde")) {
+                    return Err("--report requires building with the `serde` feature".to_string());
+                }
+                report = Some(value.clone());
+            }
+            "--manifest" => {
+                let value = iter.next().ok_or("--manifest requires a value")?;
+                if cfg!(not(feature = "serde")) {
+                    return Err("--manifest requires building with the `serde` feature".to_string());
+                }
+                manifest = Some(value.clone());
+            }
+            "--with-checksum" => with_checksum = true,
+            "--compact-binary" => compact_binary = true,
+            "--limit" => {
+                let value = iter.next().ok_or("--limit requires a value")?;
+                limit = Some(parse_int("--limit", value)?);
+            }
+            "--color" => {
+                let value = iter.next().ok_or("--color requires a value")?;
+                color = value.parse()?;
+            }
+            "--state" => {
+                let value = iter.next().ok_or("--state requires a value")?;
+                state = Some(value.parse().map_err(|e| format!("--state: {}", e))?);
+            }
+            "--preview-bytes" => {
+                let value = iter.next().ok_or("--preview-bytes requires a value")?;
+                preview_bytes = parse_int("--preview-bytes", value)?;
+            }
+            "--width" => {
+                let value = iter.next().ok_or("--width requires a value")?;
+                width = parse_int("--width", value)?;
+                if width == 0 {
+                    return Err("--width must be at least 1".to_string());
+                }
+            }
+            "--schedule" | "--next-schedule" | "--draw-hour" | "--next-price" => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("{} requires a value", arg))?;
+                match arg.as_str() {
+                    "--schedule" => overrides.schedule = Some(parse_schedule(arg, value)?),
+                    "--next-schedule" => {
+                        overrides.next_schedule = Some(parse_schedule(arg, value)?)
+                    }
+                    "--draw-hour" => {
+                        let hour: u8 = parse_int(arg, value)?;
+                        if hour > 23 {
+                            return Err(format!("--draw-hour {} is out of range 0..=23", hour));
+                        }
+                        overrides.draw_hour = Some(hour);
+                    }
+                    _ => overrides.next_price = Some(parse_int(arg, value)?),
+                }
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option '{}'", flag)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    if positional.is_empty() || positional.len() > 2 {
+        return Err("incorrect number of arguments".to_string());
+    }
+    let input = positional.remove(0);
+    let mut output_file = positional.pop();
+
+    #[cfg(feature = "serde")]
+    if json_compact {
+        if format != OutputFormat::Binary {
+            return Err("--json-compact cannot be combined with --format".to_string());
+        }
+        format = OutputFormat::JsonCompact;
+    }
+
+    if compact_binary {
+        if format != OutputFormat::Binary {
+            return Err(
+                "--compact-binary cannot be combined with --format or --json-compact".to_string(),
+            );
+        }
+        format = OutputFormat::CompactBinary;
+    }
+
+    // The base64 line always goes to stdout, reusing the `-` output path
+    if stdout_base64 {
+        if output_file.is_some() {
+            return Err("--stdout-base64 replaces <output_file>, do not give both".to_string());
+        }
+        if format != OutputFormat::Binary {
+            return Err(
+                "--stdout-base64 cannot be combined with --format, --json-compact or --compact-binary"
+                    .to_string(),
+            );
+        }
+        if gzip_out {
+            return Err("--stdout-base64 cannot be combined with --gzip-out".to_string());
+        }
+        if Path::new(&input).is_dir() {
+            return Err("--stdout-base64 does not support directory input".to_string());
+        }
+        output_file = Some("-".to_string());
+        format = OutputFormat::Base64;
+    }
+
+    if with_checksum && format != OutputFormat::Binary {
+        return Err("--with-checksum only applies to binary output".to_string());
+    }
+
+    // A directory input switches to batch mode, which derives output names itself
+    if Path::new(&input).is_dir() {
+        if output_file.is_some() {
+            return Err(
+                "use --output-dir instead of <output_file> for directory input".to_string(),
+            );
+        }
+    } else {
+        if output_file.is_none() {
+            return Err("missing <output_file>".to_string());
+        }
+        if output_dir.is_some() {
+            return Err("--output-dir is only valid when the input is a directory".to_string());
+        }
+        if output_template.is_some() {
+            return Err(
+                "--output-template is only valid when the input is a directory".to_string(),
+            );
+        }
+    }
+    if output_dir.is_some() && output_template.is_some() {
+        return Err("--output-dir cannot be combined with --output-template".to_string());
+    }
+    if report.is_some() && Path::new(&input).is_dir() {
+        return Err("--report does not support directory input".to_string());
+    }
+
+    Ok(Options {
+        input,
+        output_file,
+        output_dir,
+        output_template,
+        format,
+        dry_run,
+        allow_trailing,
+        truncate,
+        sort_winners,
+        strict,
+        force,
+        overrides,
+        #[cfg(feature = "serde")]
+        config,
+        report,
+        width,
+        gzip_out,
+        manifest,
+        quiet,
+        input_encoding,
+        explain_layout,
+        redact,
+        preview_bytes,
+        state,
+        with_checksum,
+        limit,
+        color,
+    })
+}
+
+/// Prints program usage help
+pub fn print_usage(program_name: &str) {
+    eprintln!(
+        "Usage: {} [options] <input_file> <output_file>",
+        program_name
+    );
+    eprintln!(
+        "       {} [options] --stdout-base64 <input_file>",
+        program_name
+    );
+    eprintln!(
+        "       {} [options] <input_dir> [--output-dir <dir>]",
+        program_name
+    );
+    eprintln!(
+        "       {} diff [--verbose] [--field <name>]... <a> <b>",
+        program_name
+    );
+    eprintln!("       {} verify <file>", program_name);
+    eprintln!("       {} players [--base64] <file>", program_name);
+    eprintln!(
+        "       {} winners-csv [--epochs <start>..<end>] <file>",
+        program_name
+    );
+    eprintln!("       {} stats <file>", program_name);
+    eprintln!("       {} analyze <file>", program_name);
+    eprintln!("       {} winners-for <file> <address>", program_name);
+    eprintln!("       {} merge <a> <b> <out>", program_name);
+    eprintln!("       {} validate <file>", program_name);
+    eprintln!(
+        "       {} schedule-preview [--count <n>] [--from <time>] [--second-wednesday <date>] <file>",
+        program_name
+    );
+    eprintln!(
+        "       {} simulate-rollover [--winner <id> --revenue <n> --epoch <e> --tick <t>",
+        program_name
+    );
+    eprintln!("                         [--drawn-at <time>]] <file> <out>");
+    eprintln!("       {} layout [old|extended-old|new]", program_name);
+    eprintln!("       {} --help", program_name);
+    eprintln!();
+    eprintln!("Arguments:");
+    eprintln!("  <input_file>   Path to the OldRL state file for reading");
+    eprintln!("  <output_file>  Path to the file for saving NewRL");
+    eprintln!("  <input_dir>    Directory whose contract*.* files are converted in batch");
+    eprintln!();
+    eprintln!("Commands:");
+    eprintln!("  diff <a> <b>   Compare two NewRL files field by field; exits 1 on any");
+    eprintln!("                 difference. --verbose also lists identical fields");
+    eprintln!("                 --field <name> (repeatable) compares only those fields");
+    eprintln!("  verify <file>  Decode an OldRL file and re-encode it; exits 1 unless the");
+    eprintln!("                 bytes are identical, printing the first differing offset");
+    eprintln!("  players <file> List the active players of an OldRL or NewRL file, one");
+    eprintln!("                 identity per line. --base64 prints raw base64 ids instead");
+    eprintln!("  winners-csv <file>");
+    eprintln!("                 Print the winners history, oldest first, as CSV.");
+    eprintln!("                 --epochs 100..120 keeps only winners of those epochs");
+    eprintln!("  stats <file>   Summarize players, winners, prizes and state of a file");
+    eprintln!("  validate <file>");
+    eprintln!("                 Run every consistency check on a file and report each one;");
+    eprintln!("                 exits 1 when any check fails");
+    eprintln!("  analyze <file> Estimate the gross revenue and team, distribution and burn");
+    eprintln!("                 shares implied by the recorded prizes and fee percentages");
+    eprintln!("  winners-for <file> <address>");
+    eprintln!("                 List the winnings of one identity (or base64 id), oldest first");
+    eprintln!("  merge <a> <b> <out>");
+    eprintln!("                 Write <a> with the winners of <a> and <b> combined, oldest");
+    eprintln!("                 first; warns about other fields that differ");
+    eprintln!("  schedule-preview <file>");
+    eprintln!("                 List the next draw times (UTC) of a NewRL file. --count <n>");
+    eprintln!(
+        "                 (default: {}), --from <RFC 3339 time> (default: now),",
+        DEFAULT_PREVIEW_DRAWS
+    );
+    eprintln!("                 --second-wednesday <date> decides Wednesday draws");
+    eprintln!("  simulate-rollover <file> <out>");
+    eprintln!("                 Write <file> as after the next epoch boundary: deferred price");
+    eprintln!("                 and schedule applied, players cleared. --winner <id>");
+    eprintln!("                 --revenue <n> --epoch <e> --tick <t> also records a winner,");
+    eprintln!("                 drawn on the weekday of --drawn-at <time> (default: now)");
+    eprintln!("  layout [old|extended-old|new]");
+    eprintln!("                 Print every field's byte offset and size, padding included,");
+    eprintln!("                 to check a hex dump against (default: old and new)");
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("  --format <binary|json>  Output encoding (default: binary)");
+    eprintln!("  --json-compact          Write the state as one line of JSON (NDJSON)");
+    eprintln!(
+        "  --width <n>             Separator width of the state dumps (default: {})",
+        DEFAULT_DISPLAY_WIDTH
+    );
+    eprintln!("  --limit <n>             List at most n players and n winners per dump");
+    eprintln!("  --color <auto|always|never>");
+    eprintln!("                          Color dump headers and failed checks (default: auto,");
+    eprintln!("                          off when NO_COLOR is set or not on a terminal)");
+    eprintln!(
+        "  --preview-bytes <n>     Leading input/output bytes in the debug log (default: {})",
+        DEFAULT_PREVIEW_BYTES
+    );
+    eprintln!("  --compact-binary        Write only the active players and recorded winners");
+    eprintln!("  --with-checksum         Append a 4-byte K12 checksum trailer to binary output");
+    eprintln!("  --dry-run               Read and convert, but do not write the output file");
+    eprintln!("  -q, --quiet             Print nothing on success, only errors");
+    eprintln!("  --input-base64          Read the input as base64 text instead of raw binary");
+    eprintln!(
+        "  --input-hex             Read the input as hex text (0x prefix and spaces allowed)"
+    );
+    eprintln!("  --explain-layout        Show which parts of the state grew or shrank");
+    eprintln!("  --redact                Show pseudonyms instead of addresses in the dumps");
+    eprintln!(
+        "  --force                 Accept inputs that are already NewRL and pass them through,"
+    );
+    eprintln!("                          allow the output to overwrite the input, and replace");
+    eprintln!("                          an existing output without asking");
+    eprintln!(
+        "  --config <file>         TOML file with per-input schedule/draw-hour/next-epoch values"
+    );
+    eprintln!(
+        "  --report <file>         Write a JSON summary of migrated, zeroed and dropped fields"
+    );
+    eprintln!("  --manifest <file>       Append input/output K12 digests to a JSON manifest");
+    eprintln!("  --output-dir <dir>      Batch mode: write results here instead of next to inputs");
+    eprintln!("  --output-template <t>   Batch mode: output path pattern using {{dir}}, {{stem}}");
+    eprintln!("                          and {{ext}}, e.g. {{dir}}/migrated/{{stem}}.new");
+    eprintln!("  --state <selling|locked>");
+    eprintln!("                          Force current_state of the converted state");
+    eprintln!("  --schedule <mask>       Draw weekdays, bit 0 = Wednesday ... bit 6 = Tuesday");
+    eprintln!("  --draw-hour <hour>      UTC hour of the draw, 0-23");
+    eprintln!("  --next-price <units>    Ticket price deferred to the next epoch");
+    eprintln!("  --next-schedule <mask>  Draw weekdays deferred to the next epoch");
+    eprintln!("  --strict                Fail instead of warning on an inconsistent state");
+    eprintln!("  --allow-trailing        Accept zero padding after an OldRL image");
+    eprintln!("  --gzip-out              Gzip-compress the written output");
+    eprintln!("  --truncate              Drop players past the NewRL capacity instead of failing");
+    eprintln!("  --sort-winners          Reorder a winners history that is not in draw order");
+    eprintln!();
+    eprintln!("Exit codes:");
+    eprintln!("  0  Success");
+    eprintln!("  1  Invalid arguments, diff/verify/validate found differences or failed");
+    eprintln!("     checks, a batch had failures or colliding outputs, or JSON");
+    eprintln!("     serialization failed");
+    eprintln!("  2  I/O error (missing, unreadable or unwritable file)");
+    eprintln!("  3  File size matches no known layout, input too large, non-zero");
+    eprintln!("     trailing data, or players past the NewRL capacity");
+    eprintln!("  4  Undecodable data: bad state byte, identity, base64, hex, checksum or");
+    eprintln!("     config, or an input that is already NewRL");
+    eprintln!("  5  Validation failed: fees, schedule, --strict state checks, or the");
+    eprintln!("     written file does not read back");
+    eprintln!();
+    eprintln!("Example:");
+    eprintln!("  {} contract0016.185 contract0016_new.185", program_name);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_is_expanded_per_input() {
+        let template = "{dir}/migrated/{stem}.new.{ext}";
+        assert_eq!(
+            expand_output_template(template, Path::new("states/contract0016.185")).unwrap(),
+            PathBuf::from("states/migrated/contract0016.new.185")
+        );
+        assert_eq!(
+            expand_output_template(template, Path::new("contract0017.186")).unwrap(),
+            PathBuf::from("./migrated/contract0017.new.186")
+        );
+        assert_eq!(
+            expand_output_template("out/{stem}{ext}", Path::new("a/contract")).unwrap(),
+            PathBuf::from("out/contract")
+        );
+    }
+
+    #[test]
+    fn malformed_template_is_rejected() {
+        let input = Path::new("contract0016.185");
+        assert!(expand_output_template("{name}.new", input)
+            .unwrap_err()
+            .starts_with("unknown placeholder '{name}'"));
+        assert!(expand_output_template("{stem", input)
+            .unwrap_err()
+            .starts_with("unclosed '{'"));
+    }
+}
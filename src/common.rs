@@ -1,5 +1,14 @@
+use crate::error::ConvertError;
+use crate::k12::k12_hash;
+pub use crate::k12::{k12_digest, K12Hasher};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use base64::Engine;
-use std::fmt::{self, Display, Formatter};
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
 
 /// Maximum number of players in the lottery.
 pub const RL_MAX_NUMBER_OF_PLAYERS: usize = 1024;
@@ -7,6 +16,229 @@ pub const RL_MAX_NUMBER_OF_PLAYERS: usize = 1024;
 /// Maximum number of winners in history.
 pub const RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY: usize = 1024;
 
+/// UTC weekday of a Unix timestamp in the schedule convention:
+/// 0 = Wednesday, 1 = Thursday, ..., 6 = Tuesday.
+pub fn day_of_week(unix_timestamp: u64) -> u8 {
+    // 1970-01-01 was a Thursday (1 in this convention)
+    ((unix_timestamp / 86_400 + 1) % 7) as u8
+}
+
+/// Separator length of the `Display` dumps of `OldRL` and `NewRL`.
+pub const DEFAULT_DISPLAY_WIDTH: usize = 59;
+
+/// Rendering options of the `OldRL`/`NewRL` dumps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayConfig {
+    /// Length of the `═` separator lines; titles are centered to match.
+    pub width: usize,
+    /// Show pseudonyms instead of the real addresses (see `Id::pseudonym`).
+    pub redact: bool,
+    /// Maximum number of players and of winners listed; `None` lists all.
+    pub limit: Option<usize>,
+    /// Color section headers and suspicious values with ANSI escapes.
+    pub color: bool,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_DISPLAY_WIDTH,
+            redact: false,
+            limit: None,
+            color: false,
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// `id` as shown in a dump: its identity, or a `role#hash` pseudonym when
+    /// redacting. The zero address is never redacted.
+    pub fn id(&self, role: &str, id: &Id) -> String {
+        if self.redact && !id.is_zero() {
+            id.pseudonym(role)
+        } else {
+            id.to_identity()
+        }
+    }
+
+    /// Number of list entries to show.
+    pub fn shown(&self) -> usize {
+        self.limit.unwrap_or(usize::MAX)
+    }
+
+    /// `value` in the section header style when coloring.
+    pub fn header<T: Display>(&self, value: T) -> Painted<T> {
+        Painted {
+            style: self.color.then_some(HEADER_STYLE),
+            value,
+        }
+    }
+
+    /// `value` in red when coloring and `alert` is set, e.g. a fee that is
+    /// part of a split not summing to 100%.
+    pub fn alert<T: Display>(&self, alert: bool, value: T) -> Painted<T> {
+        Painted {
+            style: (self.color && alert).then_some(ALERT_STYLE),
+            value,
+        }
+    }
+
+    /// Writes the `... and M more` footer of a list of `total` entries cut
+    /// at the limit; writes nothing when every entry was shown.
+    pub(crate) fn write_remainder(&self, f: &mut Formatter<'_>, total: usize) -> fmt::Result {
+        match total.checked_sub(self.shown()) {
+            Some(hidden) if hidden > 0 => writeln!(f, "    ... and {} more", hidden),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Bold cyan, for the section headers of the dumps.
+const HEADER_STYLE: &str = "\x1b[1;36m";
+/// Bold red, for values that fail a check.
+const ALERT_STYLE: &str = "\x1b[1;31m";
+const RESET_STYLE: &str = "\x1b[0m";
+
+/// A value shown through `DisplayConfig::header` or `DisplayConfig::alert`;
+/// without a style it displays exactly like the value itself.
+pub struct Painted<T> {
+    style: Option<&'static str>,
+    value: T,
+}
+
+impl<T: Display> Display for Painted<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.style {
+            Some(style) => write!(f, "{}{}{}", style, self.value, RESET_STYLE),
+            None => self.value.fmt(f),
+        }
+    }
+}
+
+/// Centers `title` in a line of `width` characters, without trailing spaces.
+pub(crate) fn centered(title: &str, width: usize) -> String {
+    let padding = width.saturating_sub(title.chars().count()) / 2;
+    format!("{}{}", " ".repeat(padding), title)
+}
+
+/// Lower-case hex encoding of `bytes`.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Formats bytes as a classic `hexdump -C` style dump: offset, 16 hex bytes, ASCII.
+pub fn hexdump(buf: &[u8]) -> String {
+    hexdump_at(buf, 0)
+}
+
+/// Like `hexdump`, but labels lines as if `buf` started at `base_offset`.
+pub fn hexdump_at(buf: &[u8], base_offset: usize) -> String {
+    let mut out = String::new();
+    for (line, chunk) in buf.chunks(16).enumerate() {
+        out.push_str(&format!("{:08x} ", base_offset + line * 16));
+        for i in 0..16 {
+            if i == 8 {
+                out.push(' ');
+            }
+            match chunk.get(i) {
+                Some(byte) => out.push_str(&format!(" {:02x}", byte)),
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str("  |");
+        for &byte in chunk {
+            out.push(if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            });
+        }
+        out.push_str("|\n");
+    }
+    out
+}
+
+/// Length of a textual Qubic identity: 56 body letters plus a 4-letter checksum.
+pub const IDENTITY_LENGTH: usize = 60;
+
+/// Number of identity letters encoding one 8-byte chunk of the public key.
+const IDENTITY_CHUNK_LETTERS: usize = 14;
+/// Letters encoding the 32 address bytes, before the 4 checksum letters.
+const IDENTITY_BODY_LETTERS: usize = 56;
+/// Length of 32 bytes in padded standard base64.
+const BASE64_ID_LENGTH: usize = 44;
+
+/// Errors produced while parsing an `Id` from external input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdError {
+    /// Identity string does not have exactly 60 characters.
+    InvalidLength(usize),
+    /// Character outside of `A..=Z` at the given position.
+    InvalidCharacter { position: usize, character: char },
+    /// A 14-letter chunk encodes a value that does not fit into 64 bits.
+    ChunkOverflow(usize),
+    /// Checksum letters do not match the ones computed from the body.
+    InvalidChecksum { expected: String, actual: String },
+    /// Raw address is not exactly 32 bytes long.
+    InvalidByteLength(usize),
+    /// Input is not valid standard base64.
+    InvalidBase64(String),
+    /// Input is neither a 60-letter identity nor 44 characters of base64.
+    UnrecognizedFormat(usize),
+}
+
+impl Display for IdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            IdError::InvalidLength(len) => write!(
+                f,
+                "identity must be {} characters long, got {}",
+                IDENTITY_LENGTH, len
+            ),
+            IdError::InvalidCharacter {
+                position,
+                character,
+            } => write!(
+                f,
+                "invalid character {:?} at position {} (expected A-Z)",
+                character, position
+            ),
+            IdError::ChunkOverflow(chunk) => {
+                write!(f, "identity chunk {} does not fit into 64 bits", chunk)
+            }
+            IdError::InvalidChecksum { expected, actual } => write!(
+                f,
+                "identity checksum in characters {}-{} is {} but the first {} letters give {} (a letter is mistyped)",
+                IDENTITY_BODY_LETTERS + 1,
+                IDENTITY_LENGTH,
+                actual,
+                IDENTITY_BODY_LETTERS,
+                expected
+            ),
+            IdError::InvalidByteLength(len) => {
+                write!(f, "address must be 32 bytes long, got {}", len)
+            }
+            IdError::InvalidBase64(message) => write!(f, "invalid base64: {}", message),
+            IdError::UnrecognizedFormat(len) => write!(
+                f,
+                "expected a {}-letter identity or {} characters of base64, got {} characters",
+                IDENTITY_LENGTH, BASE64_ID_LENGTH, len
+            ),
+        }
+    }
+}
+
+impl core::error::Error for IdError {}
+
+/// Serializes only the non-zero entries of an address array.
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_active_ids<S: serde::Serializer>(
+    ids: &[Id; RL_MAX_NUMBER_OF_PLAYERS],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(ids.iter().filter(|id| !id.is_zero()))
+}
+
 /// Qubic ID (address) representation - 256-bit value
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(C)]
@@ -24,14 +256,157 @@ impl Id {
         self.data.iter().all(|&b| b == 0)
     }
 
+    /// Builds an address from exactly 32 raw bytes.
+    pub fn from_bytes(b: &[u8]) -> Result<Id, IdError> {
+        let data = b
+            .try_into()
+            .map_err(|_| IdError::InvalidByteLength(b.len()))?;
+        Ok(Id { data })
+    }
+
+    /// The raw 32 address bytes, as stored in the state images.
+    pub const fn as_bytes(&self) -> &[u8; 32] {
+        &self.data
+    }
+
+    /// Decodes a standard-base64 string holding 32 raw address bytes.
+    #[cfg(feature = "std")]
+    pub fn from_base64(s: &str) -> Result<Id, IdError> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(s)
+            .map_err(|e| IdError::InvalidBase64(e.to_string()))?;
+        Id::from_bytes(&bytes)
+    }
+
+    #[cfg(feature = "std")]
     pub fn to_base64(&self) -> String {
-        base64::engine::general_purpose::STANDARD.encode(&self.data)
+        base64::engine::general_purpose::STANDARD.encode(self.as_bytes())
+    }
+
+    /// Converts the raw bytes into the canonical 60-letter Qubic identity.
+    ///
+    /// Each 8-byte little-endian chunk is written as 14 base-26 letters
+    /// (least significant first), followed by 4 letters of an 18-bit
+    /// KangarooTwelve checksum over the 32 raw bytes.
+    pub fn to_identity(&self) -> String {
+        let mut identity = String::with_capacity(IDENTITY_LENGTH);
+        for chunk in self.data.chunks_exact(8) {
+            let mut fragment = u64::from_le_bytes(chunk.try_into().unwrap());
+            for _ in 0..IDENTITY_CHUNK_LETTERS {
+                identity.push((b'A' + (fragment % 26) as u8) as char);
+                fragment /= 26;
+            }
+        }
+        identity.push_str(&self.identity_checksum());
+        identity
+    }
+
+    /// Parses a 60-letter Qubic identity, verifying its checksum.
+    pub fn from_identity(s: &str) -> Result<Id, IdError> {
+        let length = s.chars().count();
+        if length != IDENTITY_LENGTH {
+            return Err(IdError::InvalidLength(length));
+        }
+        if let Some((position, character)) =
+            s.chars().enumerate().find(|(_, c)| !c.is_ascii_uppercase())
+        {
+            return Err(IdError::InvalidCharacter {
+                position,
+                character,
+            });
+        }
+        // Only ASCII letters are left, one byte each
+        let letters = s.as_bytes();
+
+        let mut id = Id::zero();
+        for (chunk, letters) in letters[..IDENTITY_BODY_LETTERS]
+            .chunks_exact(IDENTITY_CHUNK_LETTERS)
+            .enumerate()
+        {
+            let mut fragment: u64 = 0;
+            for &letter in letters.iter().rev() {
+                fragment = fragment
+                    .checked_mul(26)
+                    .and_then(|v| v.checked_add(u64::from(letter - b'A')))
+                    .ok_or(IdError::ChunkOverflow(chunk))?;
+            }
+            id.data[chunk * 8..chunk * 8 + 8].copy_from_slice(&fragment.to_le_bytes());
+        }
+
+        let expected = id.identity_checksum();
+        let actual = &s[IDENTITY_BODY_LETTERS..];
+        if expected != actual {
+            return Err(IdError::InvalidChecksum {
+                expected,
+                actual: actual.to_string(),
+            });
+        }
+
+        Ok(id)
+    }
+
+    /// Stable stand-in for sharing dumps, e.g. `player#1a2b3c4d`: `role`
+    /// followed by the first 4 bytes of the K12 digest of the address.
+    ///
+    /// The same address always gets the same hash, whatever its role, so
+    /// relationships stay visible. It hides addresses from casual readers
+    /// only; anyone holding a candidate address can recompute the hash.
+    pub fn pseudonym(&self, role: &str) -> String {
+        format!("{}#{}", role, to_hex(&k12_digest(self.as_bytes())[..4]))
+    }
+
+    /// Whether `s` is a well-formed 60-letter identity with a matching
+    /// checksum.
+    pub fn is_valid_identity(s: &str) -> bool {
+        Id::from_identity(s).is_ok()
+    }
+
+    /// Four checksum letters: the low 18 bits of a 3-byte K12 digest in base 26.
+    fn identity_checksum(&self) -> String {
+        let mut digest = [0u8; 4];
+        digest[..3].copy_from_slice(&k12_hash(self.as_bytes(), 3));
+
+        let mut checksum = u32::from_le_bytes(digest) & 0x3FFFF;
+        let mut letters = String::with_capacity(4);
+        for _ in 0..4 {
+            letters.push((b'A' + (checksum % 26) as u8) as char);
+            checksum /= 26;
+        }
+        letters
+    }
+}
+
+impl AsRef<[u8]> for Id {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
     }
 }
 
 impl Display for Id {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str(&self.to_base64())
+        f.write_str(&self.to_identity())
+    }
+}
+
+impl FromStr for Id {
+    type Err = IdError;
+
+    /// Parses either a 60-letter Qubic identity or 32 bytes in standard
+    /// base64 (with the `std` feature), telling them apart by length.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.len() {
+            IDENTITY_LENGTH => Id::from_identity(s),
+            #[cfg(feature = "std")]
+            BASE64_ID_LENGTH => Id::from_base64(s),
+            _ => Err(IdError::UnrecognizedFormat(s.chars().count())),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Id {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_identity())
     }
 }
 
@@ -41,15 +416,449 @@ impl Default for Id {
     }
 }
 
+/// Reads `reader` to its end, but at most `size + 1` bytes: enough for the
+/// caller's size check to tell a longer input apart without reading all of it.
+#[cfg(feature = "std")]
+pub(crate) fn read_image<R: std::io::Read + ?Sized>(
+    reader: &mut R,
+    size: usize,
+) -> Result<Vec<u8>, ConvertError> {
+    use std::io::Read;
+
+    let mut buf = Vec::with_capacity(size);
+    // read_to_end retries interrupted reads and keeps going after short ones
+    reader.take(size as u64 + 1).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Reads fields at absolute offsets from a buffer whose length was already validated.
+///
+/// Multi-byte integers are decoded little-endian independent of host byte order.
+pub(crate) struct FieldReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> FieldReader<'a> {
+    /// Wraps `buf`, failing if it is not exactly `expected` bytes long.
+    pub(crate) fn new(buf: &'a [u8], expected: usize) -> Result<Self, ConvertError> {
+        if buf.len() != expected {
+            return Err(ConvertError::SizeMismatch {
+                expected,
+                actual: buf.len(),
+            });
+        }
+        Ok(Self { buf })
+    }
+
+    fn array<const N: usize>(&self, offset: usize) -> [u8; N] {
+        self.buf[offset..offset + N].try_into().unwrap()
+    }
+
+    pub(crate) fn u8(&self, offset: usize) -> u8 {
+        self.buf[offset]
+    }
+
+    pub(crate) fn u16(&self, offset: usize) -> u16 {
+        u16::from_le_bytes(self.array(offset))
+    }
+
+    pub(crate) fn u32(&self, offset: usize) -> u32 {
+        u32::from_le_bytes(self.array(offset))
+    }
+
+    pub(crate) fn u64(&self, offset: usize) -> u64 {
+        u64::from_le_bytes(self.array(offset))
+    }
+
+    pub(crate) fn id(&self, offset: usize) -> Id {
+        Id {
+            data: self.array(offset),
+        }
+    }
+
+    pub(crate) fn state(&self, offset: usize) -> Result<EState, ConvertError> {
+        EState::try_from(self.u8(offset))
+    }
+}
+
+/// Writes fields at absolute offsets into a zero-filled buffer of fixed size.
+///
+/// Multi-byte integers are written little-endian, matching the Qubic wire
+/// format regardless of host byte order; untouched bytes (padding) stay zero.
+/// A writer created with `window` covers only part of the image, but is
+/// still addressed with offsets from the start of the whole structure.
+pub(crate) struct FieldWriter {
+    buf: Vec<u8>,
+    base: usize,
+}
+
+impl FieldWriter {
+    pub(crate) fn new(size: usize) -> Self {
+        Self::window(0, size)
+    }
+
+    /// Writer for the `len` bytes of the image starting at offset `base`.
+    pub(crate) fn window(base: usize, len: usize) -> Self {
+        Self {
+            buf: vec![0; len],
+            base,
+        }
+    }
+
+    fn put(&mut self, offset: usize, bytes: &[u8]) {
+        let start = offset - self.base;
+        self.buf[start..start + bytes.len()].copy_from_slice(bytes);
+    }
+
+    pub(crate) fn u8(&mut self, offset: usize, value: u8) {
+        self.put(offset, &[value]);
+    }
+
+    pub(crate) fn u16(&mut self, offset: usize, value: u16) {
+        self.put(offset, &value.to_le_bytes());
+    }
+
+    pub(crate) fn u32(&mut self, offset: usize, value: u32) {
+        self.put(offset, &value.to_le_bytes());
+    }
+
+    pub(crate) fn u64(&mut self, offset: usize, value: u64) {
+        self.put(offset, &value.to_le_bytes());
+    }
+
+    pub(crate) fn id(&mut self, offset: usize, value: &Id) {
+        self.put(offset, &value.data);
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(u8)]
 pub enum EState {
     Selling = 0,
     Locked = 1,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for EState {
     fn default() -> Self {
         EState::Locked
     }
 }
+
+impl EState {
+    /// Name of the state, as shown in dumps and accepted by `FromStr`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EState::Selling => "Selling",
+            EState::Locked => "Locked",
+        }
+    }
+}
+
+impl Display for EState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when a state name is neither `Selling` nor `Locked`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseStateError(pub String);
+
+impl Display for ParseStateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown state '{}' (expected selling or locked)", self.0)
+    }
+}
+
+impl core::error::Error for ParseStateError {}
+
+impl FromStr for EState {
+    type Err = ParseStateError;
+
+    /// Parses a state name as returned by `as_str`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [EState::Selling, EState::Locked]
+            .into_iter()
+            .find(|state| state.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParseStateError(s.to_string()))
+    }
+}
+
+impl TryFrom<u8> for EState {
+    type Error = ConvertError;
+
+    /// Decodes a stored `current_state` byte; only 0 and 1 are valid.
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(EState::Selling),
+            1 => Ok(EState::Locked),
+            value => Err(ConvertError::InvalidState(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::Weekday;
+
+    #[test]
+    fn day_of_week_starts_on_wednesday() {
+        // 2025-01-01 was a Wednesday, the day of schedule bit 0
+        let wednesday = 1_735_689_600;
+        for (offset, day) in Weekday::ALL.into_iter().enumerate() {
+            let timestamp = wednesday + offset as u64 * 86_400;
+            assert_eq!(day_of_week(timestamp), day as u8);
+            assert_eq!(day_of_week(timestamp + 86_399), day as u8);
+        }
+        assert_eq!(day_of_week(wednesday + 7 * 86_400), 0);
+        // 1970-01-01 was a Thursday
+        assert_eq!(day_of_week(0), Weekday::Thursday as u8);
+    }
+
+    // Zero address and the QX (index 1) and QUTIL (index 4) contract addresses
+    const KNOWN_IDENTITIES: [(u8, &str); 3] = [
+        (
+            0,
+            "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAFXIB",
+        ),
+        (
+            1,
+            "BAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAARMID",
+        ),
+        (
+            4,
+            "EAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAVWRF",
+        ),
+    ];
+
+    fn contract_id(index: u8) -> Id {
+        let mut id = Id::zero();
+        id.data[0] = index;
+        id
+    }
+
+    #[test]
+    fn known_addresses_render_as_identities() {
+        for (index, identity) in KNOWN_IDENTITIES {
+            assert_eq!(contract_id(index).to_identity(), identity);
+            assert_eq!(contract_id(index).to_string(), identity);
+        }
+    }
+
+    #[test]
+    fn known_identities_parse_to_addresses() {
+        for (index, identity) in KNOWN_IDENTITIES {
+            assert_eq!(Id::from_identity(identity).unwrap(), contract_id(index));
+        }
+    }
+
+    #[test]
+    fn identity_round_trips_arbitrary_bytes() {
+        let mut id = Id::zero();
+        for (i, byte) in id.data.iter_mut().enumerate() {
+            *byte = (i as u8).wrapping_mul(37).wrapping_add(11);
+        }
+        let identity = id.to_identity();
+        assert_eq!(identity.len(), IDENTITY_LENGTH);
+        assert_eq!(Id::from_identity(&identity).unwrap(), id);
+    }
+
+    #[test]
+    fn identity_checksum_is_verified() {
+        let identity = KNOWN_IDENTITIES[1].1.replace("RMID", "RMIE");
+        assert!(matches!(
+            Id::from_identity(&identity),
+            Err(IdError::InvalidChecksum { .. })
+        ));
+    }
+
+    #[test]
+    fn fields_are_read_little_endian() {
+        let buf = [
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E,
+            0x0F,
+        ];
+        let reader = FieldReader::new(&buf, buf.len()).unwrap();
+        assert_eq!(reader.u8(0), 0x01);
+        assert_eq!(reader.u16(1), 0x0302);
+        assert_eq!(reader.u32(3), 0x0706_0504);
+        assert_eq!(reader.u64(7), 0x0F0E_0D0C_0B0A_0908);
+    }
+
+    #[test]
+    fn fields_are_written_little_endian() {
+        let mut writer = FieldWriter::new(15);
+        writer.u8(0, 0x01);
+        writer.u16(1, 0x0302);
+        writer.u32(3, 0x0706_0504);
+        writer.u64(7, 0x0F0E_0D0C_0B0A_0908);
+        assert_eq!(writer.into_bytes(), (1..=15).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn id_from_bytes_checks_the_length() {
+        let bytes: Vec<u8> = (0..32).collect();
+        assert_eq!(Id::from_bytes(&bytes).unwrap().data[..], bytes[..]);
+        assert_eq!(
+            Id::from_bytes(&bytes[..31]),
+            Err(IdError::InvalidByteLength(31))
+        );
+        assert_eq!(
+            Id::from_bytes(&[0; 33]),
+            Err(IdError::InvalidByteLength(33))
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn id_from_base64_checks_encoding_and_length() {
+        let id = Id { data: [7; 32] };
+        assert_eq!(Id::from_base64(&id.to_base64()), Ok(id));
+        // 24 bytes of valid base64
+        assert_eq!(
+            Id::from_base64("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"),
+            Err(IdError::InvalidByteLength(24))
+        );
+        assert!(matches!(
+            Id::from_base64("not base64!"),
+            Err(IdError::InvalidBase64(_))
+        ));
+    }
+
+    #[test]
+    fn state_byte_decodes_to_estate() {
+        assert_eq!(EState::try_from(0).unwrap(), EState::Selling);
+        assert_eq!(EState::try_from(1).unwrap(), EState::Locked);
+        assert!(matches!(
+            EState::try_from(255),
+            Err(ConvertError::InvalidState(255))
+        ));
+    }
+
+    #[test]
+    fn digest_of_a_known_buffer() {
+        // KangarooTwelve spec vector: 17 bytes 00 01 .. 10
+        let buf: Vec<u8> = (0..17).collect();
+        assert_eq!(
+            to_hex(&k12_digest(&buf)),
+            "6bf75fa2239198db4772e36478f8e19b0f371205f6a9a93a273f51df37122888"
+        );
+    }
+
+    #[test]
+    fn identity_is_parsed_from_str() {
+        let (index, identity) = KNOWN_IDENTITIES[1];
+        assert_eq!(identity.parse::<Id>().unwrap(), contract_id(index));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn base64_is_parsed_from_str() {
+        let base64 = "AQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+        assert_eq!(base64.parse::<Id>().unwrap(), contract_id(1));
+        assert_eq!(contract_id(1).to_base64(), base64);
+    }
+
+    #[test]
+    fn garbage_is_not_parsed_as_an_id() {
+        assert_eq!(
+            "not-an-address".parse::<Id>(),
+            Err(IdError::UnrecognizedFormat(14))
+        );
+        // Right length for an identity, but lower-case letters are not valid in one
+        let lower = KNOWN_IDENTITIES[1].1.to_lowercase();
+        assert!(matches!(
+            lower.parse::<Id>(),
+            Err(IdError::InvalidCharacter { position: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn same_address_always_gets_the_same_pseudonym() {
+        let redacting = DisplayConfig {
+            redact: true,
+            ..DisplayConfig::default()
+        };
+        let first = redacting.id("player", &contract_id(1));
+        assert_eq!(redacting.id("player", &contract_id(1)), first);
+        assert_eq!(contract_id(1).pseudonym("player"), first);
+        assert!(first.starts_with("player#") && first.len() == "player#".len() + 8);
+
+        // The hash part is the same in every role, and differs between addresses
+        let owner = redacting.id("owner", &contract_id(1));
+        assert_eq!(owner["owner".len()..], first["player".len()..]);
+        assert_ne!(redacting.id("player", &contract_id(4)), first);
+
+        assert_eq!(redacting.id("player", &Id::zero()), KNOWN_IDENTITIES[0].1);
+    }
+
+    #[test]
+    fn identity_validity_is_checked() {
+        let valid = KNOWN_IDENTITIES[2].1;
+        assert!(Id::is_valid_identity(valid));
+
+        // One body letter changed: still 60 letters, but the checksum no longer matches
+        let mut corrupted = String::from(valid);
+        corrupted.replace_range(10..11, "B");
+        assert!(!Id::is_valid_identity(&corrupted));
+        assert!(matches!(
+            Id::from_identity(&corrupted),
+            Err(IdError::InvalidChecksum { .. })
+        ));
+
+        let short = &valid[..59];
+        assert!(!Id::is_valid_identity(short));
+        assert_eq!(Id::from_identity(short), Err(IdError::InvalidLength(59)));
+
+        // 60 characters but 61 bytes: the letter is reported, not the length
+        let accented = alloc::format!("É{}", &valid[1..]);
+        assert_eq!(
+            Id::from_identity(&accented),
+            Err(IdError::InvalidCharacter {
+                position: 0,
+                character: 'É',
+            })
+        );
+    }
+
+    #[test]
+    fn state_names_parse_ignoring_case() {
+        for (name, state) in [
+            ("Selling", EState::Selling),
+            ("selling", EState::Selling),
+            ("LOCKED", EState::Locked),
+            ("locked", EState::Locked),
+        ] {
+            assert_eq!(name.parse::<EState>(), Ok(state));
+        }
+        assert_eq!(EState::Selling.as_str(), "Selling");
+        assert_eq!(EState::Locked.as_str(), "Locked");
+    }
+
+    #[test]
+    fn unknown_state_name_is_rejected() {
+        assert_eq!(
+            "paused".parse::<EState>(),
+            Err(ParseStateError("paused".to_string()))
+        );
+    }
+
+    #[test]
+    fn byte_views_match_the_data() {
+        let mut id = Id::zero();
+        for (i, byte) in id.data.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        assert_eq!(id.as_bytes(), &id.data);
+        let bytes: &[u8] = id.as_ref();
+        assert_eq!(bytes, &id.data[..]);
+        assert_eq!(k12_digest(id.as_ref()), k12_digest(&id.data));
+    }
+}
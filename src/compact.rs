@@ -0,0 +1,248 @@
+//! Compact encoding of a `NewRL` (`--compact-binary`): the scalar fields
+//! followed by only the active players and the recorded winners.
+//!
+//! The encoding keeps every value but not every position. Players are
+//! packed into the first slots on decoding, and the winners are laid out
+//! in front of `winners_counter` so `winners_chronological` yields them in
+//! the same order. A state whose players are packed and whose history has
+//! no cleared slots decodes to exactly the original.
+//!
+//! All integers are little-endian:
+//!
+//! | Bytes | Content |
+//! |---|---|
+//! | 4 | `COMPACT_MAGIC` |
+//! | 32 + 32 | `team_address`, `owner_address` |
+//! | 8 + 1 | `next_epoch_data.new_price`, `next_epoch_data.schedule` |
+//! | 8 + 8 + 8 | `ticket_price`, `player_counter`, `winners_counter` |
+//! | 1 + 1 + 4 | `last_draw_day`, `last_draw_hour`, `last_draw_date_stamp` |
+//! | 4 | team, distribution, winner and burn percentages |
+//! | 1 + 1 + 1 | `schedule`, `draw_hour`, `current_state` |
+//! | 2 + 32 each | player count, then the active player ids in slot order |
+//! | 2 + 47 each | winner count, then address, revenue, tick, epoch and day of week of each recorded winner, oldest first |
+
+use crate::common::{EState, Id, RL_MAX_NUMBER_OF_PLAYERS, RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY};
+use crate::error::ConvertError;
+use crate::new_rl::{NewRL, NextEpochData, WinnerInfoNew};
+use alloc::vec::Vec;
+
+/// First bytes of every compact image.
+pub const COMPACT_MAGIC: [u8; 4] = *b"RLC1";
+
+/// Whether `buf` starts like a compact image.
+pub fn is_compact(buf: &[u8]) -> bool {
+    buf.starts_with(&COMPACT_MAGIC)
+}
+
+/// Encodes `new_rl` in the compact format.
+pub fn to_compact_bytes(new_rl: &NewRL) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&COMPACT_MAGIC);
+    out.extend_from_slice(new_rl.team_address.as_bytes());
+    out.extend_from_slice(new_rl.owner_address.as_bytes());
+    out.extend_from_slice(&new_rl.next_epoch_data.new_price.to_le_bytes());
+    out.push(new_rl.next_epoch_data.schedule);
+    out.extend_from_slice(&new_rl.ticket_price.to_le_bytes());
+    out.extend_from_slice(&new_rl.player_counter.to_le_bytes());
+    out.extend_from_slice(&new_rl.winners_counter.to_le_bytes());
+    out.push(new_rl.last_draw_day);
+    out.push(new_rl.last_draw_hour);
+    out.extend_from_slice(&new_rl.last_draw_date_stamp.to_le_bytes());
+    out.extend_from_slice(&[
+        new_rl.team_fee_percent,
+        new_rl.distribution_fee_percent,
+        new_rl.winner_fee_percent,
+        new_rl.burn_percent,
+        new_rl.schedule,
+        new_rl.draw_hour,
+        new_rl.current_state as u8,
+    ]);
+
+    // Both counts fit: the arrays hold at most 1024 entries
+    let players: Vec<&Id> = new_rl.players_iter().collect();
+    out.extend_from_slice(&(players.len() as u16).to_le_bytes());
+    for player in players {
+        out.extend_from_slice(player.as_bytes());
+    }
+
+    let winners: Vec<&WinnerInfoNew> = new_rl.winners_chronological().collect();
+    out.extend_from_slice(&(winners.len() as u16).to_le_bytes());
+    for winner in winners {
+        out.extend_from_slice(winner.winner_address.as_bytes());
+        out.extend_from_slice(&winner.revenue.to_le_bytes());
+        out.extend_from_slice(&winner.tick.to_le_bytes());
+        out.extend_from_slice(&winner.epoch.to_le_bytes());
+        out.push(winner.day_of_week);
+    }
+    out
+}
+
+/// Decodes a compact image, zero-filling the slots it does not list.
+pub fn from_compact_bytes(buf: &[u8]) -> Result<NewRL, ConvertError> {
+    let mut cursor = Cursor { buf, pos: 0 };
+    if cursor.take::<4>()? != COMPACT_MAGIC {
+        return Err(ConvertError::InvalidCompact("missing RLC1 magic"));
+    }
+
+    let mut new_rl = NewRL {
+        team_address: cursor.id()?,
+        owner_address: cursor.id()?,
+        next_epoch_data: NextEpochData {
+            new_price: cursor.u64()?,
+            schedule: cursor.u8()?,
+        },
+        ticket_price: cursor.u64()?,
+        player_counter: cursor.u64()?,
+        winners_counter: cursor.u64()?,
+        last_draw_day: cursor.u8()?,
+        last_draw_hour: cursor.u8()?,
+        last_draw_date_stamp: u32::from_le_bytes(cursor.take()?),
+        team_fee_percent: cursor.u8()?,
+        distribution_fee_percent: cursor.u8()?,
+        winner_fee_percent: cursor.u8()?,
+        burn_percent: cursor.u8()?,
+        schedule: cursor.u8()?,
+        draw_hour: cursor.u8()?,
+        current_state: EState::try_from(cursor.u8()?)?,
+        ..NewRL::default()
+    };
+
+    let players = cursor.count(RL_MAX_NUMBER_OF_PLAYERS, "more players than slots")?;
+    for slot in &mut new_rl.players[..players] {
+        *slot = cursor.id()?;
+    }
+
+    // Entry i of n goes n - i slots before winners_counter, so the oldest
+    // entry is the first one winners_chronological reaches
+    let winners = cursor.count(
+        RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY,
+        "more winners than history slots",
+    )?;
+    let first = new_rl.winners_counter.wrapping_sub(winners as u64);
+    for i in 0..winners {
+        let slot =
+            (first.wrapping_add(i as u64) % RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY as u64) as usize;
+        new_rl.winners[slot] = WinnerInfoNew {
+            winner_address: cursor.id()?,
+            revenue: cursor.u64()?,
+            tick: u32::from_le_bytes(cursor.take()?),
+            epoch: u16::from_le_bytes(cursor.take()?),
+            day_of_week: cursor.u8()?,
+        };
+    }
+
+    if cursor.pos != buf.len() {
+        return Err(ConvertError::InvalidCompact(
+            "trailing bytes after the winners",
+        ));
+    }
+    Ok(new_rl)
+}
+
+/// Sequential little-endian reader over a compact image.
+struct Cursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ConvertError> {
+        let bytes = self
+            .buf
+            .get(self.pos..self.pos + N)
+            .ok_or(ConvertError::InvalidCompact("image ends early"))?;
+        self.pos += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, ConvertError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, ConvertError> {
+        Ok(u64::from_le_bytes(self.take()?))
+    }
+
+    fn id(&mut self) -> Result<Id, ConvertError> {
+        Ok(Id { data: self.take()? })
+    }
+
+    /// Entry count, rejected above `max`.
+    fn count(&mut self, max: usize, too_many: &'static str) -> Result<usize, ConvertError> {
+        let count = usize::from(u16::from_le_bytes(self.take()?));
+        if count > max {
+            return Err(ConvertError::InvalidCompact(too_many));
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(byte: u8) -> Id {
+        Id { data: [byte; 32] }
+    }
+
+    /// A selling state whose players are packed and whose history has wrapped.
+    fn packed_state() -> NewRL {
+        let mut new_rl = NewRL::builder()
+            .team_address(id(0xAA))
+            .owner_address(id(0xBB))
+            .next_epoch_data(NextEpochData::new(2_000_000, 0b0000_0001))
+            .team_fee_percent(10)
+            .distribution_fee_percent(20)
+            .winner_fee_percent(50)
+            .burn_percent(20)
+            .ticket_price(1_000_000)
+            .schedule(0b0000_1001)
+            .draw_hour(12)
+            .current_state(EState::Selling)
+            .build()
+            .unwrap();
+        new_rl.players[0] = id(1);
+        new_rl.players[1] = id(2);
+        new_rl.player_counter = 2;
+        for n in 1..=RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY + 3 {
+            new_rl.record_winner(WinnerInfoNew::new(id(3), n as u64, n as u32, n as u16, 3));
+        }
+        new_rl.last_draw_date_stamp = 20_250_105;
+        new_rl
+    }
+
+    #[test]
+    fn packed_state_round_trips_exactly() {
+        let new_rl = packed_state();
+        let image = to_compact_bytes(&new_rl);
+        assert!(is_compact(&image));
+        assert_eq!(from_compact_bytes(&image).unwrap(), new_rl);
+    }
+
+    #[test]
+    fn spread_players_keep_their_content() {
+        let mut new_rl = packed_state();
+        new_rl.players[1] = Id::zero();
+        new_rl.players[700] = id(2);
+
+        let decoded = from_compact_bytes(&to_compact_bytes(&new_rl)).unwrap();
+        assert_eq!(decoded.players[..2], [id(1), id(2)]);
+        assert!(decoded.players_iter().eq(new_rl.players_iter()));
+        assert!(decoded
+            .winners_chronological()
+            .eq(new_rl.winners_chronological()));
+    }
+
+    #[test]
+    fn truncated_image_is_rejected() {
+        let image = to_compact_bytes(&packed_state());
+        assert!(matches!(
+            from_compact_bytes(&image[..image.len() - 1]),
+            Err(ConvertError::InvalidCompact("image ends early"))
+        ));
+        assert!(matches!(
+            from_compact_bytes(&image[4..]),
+            Err(ConvertError::InvalidCompact("missing RLC1 magic"))
+        ));
+    }
+}
@@ -0,0 +1,107 @@
+//! TOML migration configs supplying new-only field values per input file.
+//!
+//! ```toml
+//! [default]
+//! schedule = 0b0001001
+//! draw_hour = 11
+//!
+//! [files."contract0016.185"]
+//! draw_hour = 12
+//! next_price = 1000000
+//! ```
+//!
+//! Keys of `[files]` are matched against the input's file name. A file entry
+//! only needs the values that differ; anything it leaves out comes from
+//! `[default]`.
+
+use crate::error::ConvertError;
+use crate::new_rl::NewFieldOverrides;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Parsed `--config` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MigrationConfig {
+    /// Values for inputs without an entry of their own.
+    #[serde(default)]
+    pub default: NewFieldOverrides,
+    /// Values for specific inputs, keyed by file name.
+    #[serde(default)]
+    pub files: HashMap<String, NewFieldOverrides>,
+}
+
+impl MigrationConfig {
+    /// Parses a config and validates every entry's hour and bitmasks.
+    pub fn from_toml(text: &str) -> Result<MigrationConfig, ConvertError> {
+        let config: MigrationConfig =
+            toml::from_str(text).map_err(|e| ConvertError::Config(e.to_string()))?;
+
+        config.default.validate()?;
+        for overrides in config.files.values() {
+            overrides.validate()?;
+        }
+        Ok(config)
+    }
+
+    /// Values for `input`: its own entry completed from `[default]`.
+    pub fn overrides_for(&self, input: &Path) -> NewFieldOverrides {
+        input
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| self.files.get(name))
+            .map_or(self.default, |entry| entry.or(self.default))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::new_rl::{NewRL, ScheduleError};
+
+    const CONFIG: &str = r#"
+[default]
+schedule = 0b0001001
+draw_hour = 11
+
+[files."contract0016.185"]
+draw_hour = 12
+next_price = 1000000
+"#;
+
+    #[test]
+    fn file_entry_is_applied_over_the_default() {
+        let config = MigrationConfig::from_toml(CONFIG).unwrap();
+
+        let mut matched = NewRL::default();
+        config
+            .overrides_for(Path::new("states/contract0016.185"))
+            .apply_to(&mut matched);
+        assert_eq!(matched.schedule, 0b0001001);
+        assert_eq!(matched.draw_hour, 12);
+        assert_eq!(matched.next_epoch_data.new_price, 1_000_000);
+
+        let mut other = NewRL::default();
+        config
+            .overrides_for(Path::new("contract0016.186"))
+            .apply_to(&mut other);
+        assert_eq!(other.schedule, 0b0001001);
+        assert_eq!(other.draw_hour, 11);
+        assert_eq!(other.next_epoch_data.new_price, 0);
+    }
+
+    #[test]
+    fn out_of_range_hour_is_rejected() {
+        let error = MigrationConfig::from_toml("[files.\"a\"]\ndraw_hour = 24\n").unwrap_err();
+        assert!(matches!(
+            error,
+            ConvertError::InvalidSchedule(ScheduleError::InvalidDrawHour(24))
+        ));
+    }
+
+    #[test]
+    fn unknown_key_is_rejected() {
+        let error = MigrationConfig::from_toml("[default]\ndraw_day = 3\n").unwrap_err();
+        assert!(matches!(error, ConvertError::Config(_)));
+    }
+}
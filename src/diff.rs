@@ -0,0 +1,238 @@
+//! Field-by-field comparison of two `NewRL` states.
+
+use crate::common::Id;
+use crate::new_rl::{NewRL, WinnerInfoNew};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+/// One compared field with both sides rendered as text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldComparison {
+    /// Field path, e.g. `team_fee_percent` or `winners[3]`.
+    pub field: String,
+    pub left: String,
+    pub right: String,
+}
+
+impl FieldComparison {
+    fn new(field: impl Into<String>, left: impl ToString, right: impl ToString) -> Self {
+        Self {
+            field: field.into(),
+            left: left.to_string(),
+            right: right.to_string(),
+        }
+    }
+
+    pub fn is_equal(&self) -> bool {
+        self.left == self.right
+    }
+
+    /// Whether this comparison is `name` itself or one of its slots or
+    /// subfields, e.g. `winners[3]` for `winners`.
+    pub fn belongs_to(&self, name: &str) -> bool {
+        match self.field.strip_prefix(name) {
+            Some(rest) => rest.is_empty() || rest.starts_with('[') || rest.starts_with('.'),
+            None => false,
+        }
+    }
+}
+
+/// Names accepted by `compare_fields`: every scalar of `compare_scalars`,
+/// the `next_epoch_data` group and the `players` and `winners` arrays.
+pub const FIELD_NAMES: [&str; 20] = [
+    "team_address",
+    "owner_address",
+    "team_fee_percent",
+    "distribution_fee_percent",
+    "winner_fee_percent",
+    "burn_percent",
+    "ticket_price",
+    "player_counter",
+    "winners_counter",
+    "schedule",
+    "draw_hour",
+    "last_draw_day",
+    "last_draw_hour",
+    "last_draw_date_stamp",
+    "next_epoch_data",
+    "next_epoch_data.new_price",
+    "next_epoch_data.schedule",
+    "current_state",
+    "players",
+    "winners",
+];
+
+fn id_summary(id: &Id) -> String {
+    if id.is_zero() {
+        "<empty>".to_string()
+    } else {
+        id.to_string()
+    }
+}
+
+fn winner_summary(w: &WinnerInfoNew) -> String {
+    if w.winner_address.is_zero() {
+        return "<empty>".to_string();
+    }
+    format!(
+        "{} revenue={} epoch={} tick={} day={}",
+        w.winner_address, w.revenue, w.epoch, w.tick, w.day_of_week
+    )
+}
+
+/// Compares every scalar field and every winner/player slot that is
+/// non-zero on at least one side. Slots empty on both sides are skipped.
+pub fn compare(a: &NewRL, b: &NewRL) -> Vec<FieldComparison> {
+    let mut fields = compare_scalars(a, b);
+
+    for (i, (left, right)) in a.players.iter().zip(b.players.iter()).enumerate() {
+        if !left.is_zero() || !right.is_zero() {
+            fields.push(FieldComparison::new(
+                format!("players[{}]", i),
+                id_summary(left),
+                id_summary(right),
+            ));
+        }
+    }
+
+    for (i, (left, right)) in a.winners.iter().zip(b.winners.iter()).enumerate() {
+        if !left.winner_address.is_zero() || !right.winner_address.is_zero() {
+            fields.push(FieldComparison::new(
+                format!("winners[{}]", i),
+                winner_summary(left),
+                winner_summary(right),
+            ));
+        }
+    }
+
+    fields
+}
+
+/// Like `compare`, but keeps only the comparisons belonging to one of
+/// `names` (see `FieldComparison::belongs_to`); an empty `names` keeps all.
+pub fn compare_fields<S: AsRef<str>>(a: &NewRL, b: &NewRL, names: &[S]) -> Vec<FieldComparison> {
+    let mut fields = compare(a, b);
+    if !names.is_empty() {
+        fields.retain(|field| names.iter().any(|name| field.belongs_to(name.as_ref())));
+    }
+    fields
+}
+
+/// Compares the scalar fields only: addresses, fees, price, counters,
+/// schedule, draw guard, next epoch data and state.
+pub fn compare_scalars(a: &NewRL, b: &NewRL) -> Vec<FieldComparison> {
+    vec![
+        FieldComparison::new("team_address", a.team_address, b.team_address),
+        FieldComparison::new("owner_address", a.owner_address, b.owner_address),
+        FieldComparison::new("team_fee_percent", a.team_fee_percent, b.team_fee_percent),
+        FieldComparison::new(
+            "distribution_fee_percent",
+            a.distribution_fee_percent,
+            b.distribution_fee_percent,
+        ),
+        FieldComparison::new(
+            "winner_fee_percent",
+            a.winner_fee_percent,
+            b.winner_fee_percent,
+        ),
+        FieldComparison::new("burn_percent", a.burn_percent, b.burn_percent),
+        FieldComparison::new("ticket_price", a.ticket_price, b.ticket_price),
+        FieldComparison::new("player_counter", a.player_counter, b.player_counter),
+        FieldComparison::new("winners_counter", a.winners_counter, b.winners_counter),
+        FieldComparison::new(
+            "schedule",
+            format!("0b{:08b}", a.schedule),
+            format!("0b{:08b}", b.schedule),
+        ),
+        FieldComparison::new("draw_hour", a.draw_hour, b.draw_hour),
+        FieldComparison::new("last_draw_day", a.last_draw_day, b.last_draw_day),
+        FieldComparison::new("last_draw_hour", a.last_draw_hour, b.last_draw_hour),
+        FieldComparison::new(
+            "last_draw_date_stamp",
+            a.last_draw_date_stamp,
+            b.last_draw_date_stamp,
+        ),
+        FieldComparison::new(
+            "next_epoch_data.new_price",
+            a.next_epoch_data.new_price,
+            b.next_epoch_data.new_price,
+        ),
+        FieldComparison::new(
+            "next_epoch_data.schedule",
+            a.next_epoch_data.schedule,
+            b.next_epoch_data.schedule,
+        ),
+        FieldComparison::new(
+            "current_state",
+            a.current_state.as_str(),
+            b.current_state.as_str(),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn differing_pair() -> (NewRL, NewRL) {
+        let a = NewRL {
+            owner_address: Id { data: [1; 32] },
+            team_fee_percent: 10,
+            winners_counter: 1,
+            ..NewRL::default()
+        };
+        let mut b = NewRL {
+            owner_address: Id { data: [2; 32] },
+            team_fee_percent: 15,
+            winners_counter: 2,
+            ..a
+        };
+        b.players[4] = Id { data: [3; 32] };
+        (a, b)
+    }
+
+    #[test]
+    fn single_field_diff_ignores_other_differences() {
+        let (a, b) = differing_pair();
+        let fields = compare_fields(&a, &b, &["owner_address"]);
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].field, "owner_address");
+        assert!(!fields[0].is_equal());
+
+        let fields = compare_fields(&a, &b, &["ticket_price"]);
+        assert_eq!(fields.len(), 1);
+        assert!(fields[0].is_equal());
+    }
+
+    #[test]
+    fn array_field_selects_its_slots_only() {
+        let (a, b) = differing_pair();
+        let fields: Vec<String> = compare_fields(&a, &b, &["players", "winners"])
+            .into_iter()
+            .map(|field| field.field)
+            .collect();
+        // `winners` does not select `winners_counter`
+        assert_eq!(fields, ["players[4]"]);
+    }
+
+    #[test]
+    fn no_selection_compares_everything() {
+        let (a, b) = differing_pair();
+        let none: [&str; 0] = [];
+        let differing: Vec<String> = compare_fields(&a, &b, &none)
+            .into_iter()
+            .filter(|field| !field.is_equal())
+            .map(|field| field.field)
+            .collect();
+        assert_eq!(
+            differing,
+            [
+                "owner_address",
+                "team_fee_percent",
+                "winners_counter",
+                "players[4]"
+            ]
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use crate::common::{to_hex, IdError};
+use crate::new_rl::{BuildError, FeeError, ScheduleError, StateError, NEW_RL_SIZE};
+use crate::old_rl::{DrawGuard, OLD_RL_SIZE};
+use crate::CHECKSUM_LEN;
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display, Formatter};
+
+/// Errors produced while reading, converting, validating or writing contract state.
+#[derive(Debug)]
+pub enum ConvertError {
+    /// Underlying file or stream operation failed.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// `--input-base64` input is not valid standard base64.
+    InvalidBase64(String),
+    /// `--input-hex` input is not an even number of hex digits.
+    InvalidHex(String),
+    /// Input length differs from the size of the target structure.
+    SizeMismatch { expected: usize, actual: usize },
+    /// Input length matches neither the OldRL nor the NewRL layout.
+    UnrecognizedSize(usize),
+    /// Input holds more bytes than the given limit; reading stopped there.
+    InputTooLarge(usize),
+    /// Source players sit in slots the NewRL array does not have (`--truncate`).
+    PlayerCapacity { dropped: usize, capacity: usize },
+    /// Bytes past the expected size are not all zero (`--allow-trailing`).
+    NonZeroTrailing { expected: usize, offset: usize },
+    /// Output file exists and overwriting it was not confirmed.
+    OutputExists(String),
+    /// Batch output resolves to one of the batch inputs and `--force` was not given.
+    OutputIsInput { output: String, input: String },
+    /// Two batch inputs would be written to the same output.
+    DuplicateOutput {
+        output: String,
+        first: String,
+        second: String,
+    },
+    /// Input is already a NewRL state and `--force` was not given.
+    AlreadyConverted,
+    /// `current_state` byte is not a valid `EState` discriminant.
+    InvalidState(u8),
+    /// `--compact-binary` image is truncated or malformed.
+    InvalidCompact(&'static str),
+    /// Textual address could not be parsed as a Qubic identity.
+    InvalidIdentity(String),
+    /// Fee percentages do not form a 100% split.
+    InvalidFees(FeeError),
+    /// Draw hour or schedule bitmask out of range.
+    InvalidSchedule(ScheduleError),
+    /// `current_state` contradicts the other fields (rejected under `--strict`).
+    InconsistentState(StateError),
+    /// Written output does not decode back to the converted state.
+    VerificationFailed,
+    /// Checksum trailer of a NewRL file does not match its body.
+    ChecksumMismatch {
+        /// Checksum computed from the body.
+        expected: [u8; CHECKSUM_LEN],
+        /// Checksum stored in the trailer.
+        actual: [u8; CHECKSUM_LEN],
+    },
+    /// JSON serialization failed.
+    #[cfg(feature = "serde")]
+    Json(serde_json::Error),
+    /// Migration config is not valid TOML or has unknown keys.
+    #[cfg(feature = "serde")]
+    Config(String),
+}
+
+impl ConvertError {
+    /// Process exit code for this category of failure: 1 for a failed JSON
+    /// serialization or colliding batch outputs, 2 for I/O, 3 for a file of the wrong size, 4 for
+    /// undecodable or unexpected data and 5 for a state that fails validation.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            #[cfg(feature = "std")]
+            ConvertError::Io(_) => 2,
+            ConvertError::OutputExists(_) => 2,
+            #[cfg(feature = "serde")]
+            ConvertError::Json(_) => 1,
+            ConvertError::OutputIsInput { .. } | ConvertError::DuplicateOutput { .. } => 1,
+            ConvertError::SizeMismatch { .. }
+            | ConvertError::UnrecognizedSize(_)
+            | ConvertError::InputTooLarge(_)
+            | ConvertError::PlayerCapacity { .. }
+            | ConvertError::NonZeroTrailing { .. } => 3,
+            ConvertError::InvalidBase64(_)
+            | ConvertError::InvalidHex(_)
+            | ConvertError::AlreadyConverted
+            | ConvertError::InvalidState(_)
+            | ConvertError::InvalidIdentity(_)
+            | ConvertError::InvalidCompact(_)
+            | ConvertError::ChecksumMismatch { .. } => 4,
+            #[cfg(feature = "serde")]
+            ConvertError::Config(_) => 4,
+            ConvertError::InvalidFees(_)
+            | ConvertError::InvalidSchedule(_)
+            | ConvertError::InconsistentState(_)
+            | ConvertError::VerificationFailed => 5,
+        }
+    }
+}
+
+impl Display for ConvertError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            ConvertError::Io(e) => write!(f, "I/O error: {}", e),
+            ConvertError::InvalidBase64(message) => write!(f, "Invalid base64 input: {}", message),
+            ConvertError::InvalidHex(message) => write!(f, "Invalid hex input: {}", message),
+            ConvertError::SizeMismatch { expected, actual } => write!(
+                f,
+                "Expected size {} bytes does not match file size {} bytes",
+                expected, actual
+            ),
+            ConvertError::UnrecognizedSize(actual) => write!(
+                f,
+                "File size {} bytes matches neither OldRL ({} bytes), OldRL with draw-guard trailer ({} bytes) nor NewRL ({} bytes)",
+                actual,
+                OLD_RL_SIZE,
+                DrawGuard::EXTENDED_OLD_SIZE,
+                NEW_RL_SIZE
+            ),
+            ConvertError::InputTooLarge(limit) => write!(
+                f,
+                "Input is longer than {} bytes, more than any state layout needs (stopped reading there)",
+                limit
+            ),
+            ConvertError::PlayerCapacity { dropped, capacity } => write!(
+                f,
+                "{} player(s) sit past the {} player slots of NewRL (use --truncate to drop them)",
+                dropped, capacity
+            ),
+            ConvertError::NonZeroTrailing { expected, offset } => write!(
+                f,
+                "Trailing data after the expected {} bytes is not zero padding (first non-zero byte at offset {})",
+                expected, offset
+            ),
+            ConvertError::OutputExists(path) => write!(
+                f,
+                "Output file '{}' already exists (use --force to overwrite)",
+                path
+            ),
+            ConvertError::OutputIsInput { output, input } => write!(
+                f,
+                "Output '{}' resolves to the input '{}'; refusing to overwrite the source (use --force to allow)",
+                output, input
+            ),
+            ConvertError::DuplicateOutput {
+                output,
+                first,
+                second,
+            } => write!(
+                f,
+                "'{}' and '{}' would both be written to '{}', fix --output-template",
+                first, second, output
+            ),
+            ConvertError::AlreadyConverted => {
+                f.write_str("Input is already a NewRL state (use --force to pass it through)")
+            }
+            ConvertError::InvalidState(value) => write!(
+                f,
+                "Invalid contract state {} (expected 0 = Selling or 1 = Locked)",
+                value
+            ),
+            ConvertError::InvalidCompact(reason) => write!(f, "Invalid compact state: {}", reason),
+            ConvertError::InvalidIdentity(message) => write!(f, "Invalid identity: {}", message),
+            ConvertError::InvalidFees(e) => write!(f, "Invalid fees: {}", e),
+            ConvertError::InvalidSchedule(e) => write!(f, "Invalid schedule: {}", e),
+            ConvertError::InconsistentState(e) => write!(f, "Inconsistent state: {}", e),
+            ConvertError::VerificationFailed => {
+                f.write_str("Verification failed: written file does not match the converted state")
+            }
+            ConvertError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "NewRL checksum mismatch: trailer holds {} but the body hashes to {} (the file is corrupted)",
+                to_hex(actual),
+                to_hex(expected)
+            ),
+            #[cfg(feature = "serde")]
+            ConvertError::Json(e) => write!(f, "JSON serialization error: {}", e),
+            #[cfg(feature = "serde")]
+            ConvertError::Config(message) => write!(f, "Invalid migration config: {}", message),
+        }
+    }
+}
+
+impl core::error::Error for ConvertError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            ConvertError::Io(e) => Some(e),
+            ConvertError::InvalidFees(e) => Some(e),
+            ConvertError::InvalidSchedule(e) => Some(e),
+            ConvertError::InconsistentState(e) => Some(e),
+            #[cfg(feature = "serde")]
+            ConvertError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for ConvertError {
+    fn from(e: std::io::Error) -> Self {
+        ConvertError::Io(e)
+    }
+}
+
+impl From<IdError> for ConvertError {
+    fn from(e: IdError) -> Self {
+        ConvertError::InvalidIdentity(e.to_string())
+    }
+}
+
+impl From<FeeError> for ConvertError {
+    fn from(e: FeeError) -> Self {
+        ConvertError::InvalidFees(e)
+    }
+}
+
+impl From<ScheduleError> for ConvertError {
+    fn from(e: ScheduleError) -> Self {
+        ConvertError::InvalidSchedule(e)
+    }
+}
+
+impl From<StateError> for ConvertError {
+    fn from(e: StateError) -> Self {
+        ConvertError::InconsistentState(e)
+    }
+}
+
+impl From<BuildError> for ConvertError {
+    fn from(e: BuildError) -> Self {
+        match e {
+            BuildError::InvalidFees(e) => ConvertError::InvalidFees(e),
+            BuildError::InvalidSchedule(e) => ConvertError::InvalidSchedule(e),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for ConvertError {
+    fn from(e: serde_json::Error) -> Self {
+        ConvertError::Json(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_category_has_its_own_exit_code() {
+        assert_eq!(
+            ConvertError::SizeMismatch {
+                expected: OLD_RL_SIZE,
+                actual: 10
+            }
+            .exit_code(),
+            3
+        );
+        assert_eq!(ConvertError::UnrecognizedSize(10).exit_code(), 3);
+        assert_eq!(ConvertError::InvalidState(7).exit_code(), 4);
+        assert_eq!(ConvertError::AlreadyConverted.exit_code(), 4);
+        let fees = FeeError {
+            team: 10,
+            distribution: 0,
+            burn: 0,
+            winner: 0,
+        };
+        assert_eq!(ConvertError::InvalidFees(fees).exit_code(), 5);
+        assert_eq!(ConvertError::VerificationFailed.exit_code(), 5);
+        assert_eq!(ConvertError::OutputExists("out".to_string()).exit_code(), 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn io_errors_exit_with_2() {
+        let error = ConvertError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(error.exit_code(), 2);
+    }
+}
@@ -0,0 +1,132 @@
+//! Random but reproducible NewRL states for exercising the other commands
+//! (`gen`, only built with the `gen` feature).
+
+use crate::cli::GenOptions;
+use rlconverter::common::{EState, Id, RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY};
+use rlconverter::new_rl::{NewRL, NextEpochData, WinnerInfoNew};
+
+/// SplitMix64: tiny, seedable and good enough for test data
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform-enough value in `0..bound`; `bound` must not be 0
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// Non-zero address
+    fn id(&mut self) -> Id {
+        let mut id = Id::zero();
+        while id.is_zero() {
+            for chunk in id.data.chunks_exact_mut(8) {
+                chunk.copy_from_slice(&self.next_u64().to_le_bytes());
+            }
+        }
+        id
+    }
+}
+
+/// Builds a selling state with `options.players` players and
+/// `options.winners` winners; the same seed always yields the same state
+pub fn generate(options: &GenOptions) -> NewRL {
+    let mut rng = Rng(options.seed);
+    let team_address = rng.id();
+    let owner_address = rng.id();
+
+    // Winner share 50..=90%, the rest split between team, distribution and burn
+    let winner = 50 + rng.below(41) as u8;
+    let team = rng.below(u64::from(100 - winner) + 1) as u8;
+    let distribution = rng.below(u64::from(100 - winner - team) + 1) as u8;
+
+    let ticket_price = (1 + rng.below(10)) * 1_000_000;
+    let mut new_rl = NewRL::builder()
+        .team_address(team_address)
+        .owner_address(owner_address)
+        .next_epoch_data(NextEpochData::new(ticket_price, options.schedule))
+        .ticket_price(ticket_price)
+        .winner_fee_percent(winner)
+        .team_fee_percent(team)
+        .distribution_fee_percent(distribution)
+        .burn_percent(100 - winner - team - distribution)
+        .schedule(options.schedule)
+        .draw_hour(rng.below(24) as u8)
+        .current_state(EState::Selling)
+        .build()
+        .expect("generated fees add up to 100% and the schedule was validated");
+
+    for slot in &mut new_rl.players[..options.players] {
+        *slot = rng.id();
+    }
+    new_rl.player_counter = options.players as u64;
+
+    // Draws happen in chronological order, and only on scheduled days
+    let days: Vec<u8> = (0..7)
+        .filter(|d| options.schedule & (1 << d) != 0)
+        .collect();
+    let mut draws: Vec<(u16, u32)> = (0..options.winners)
+        .map(|_| {
+            (
+                100 + rng.below(100) as u16,
+                rng.below(u64::from(u32::MAX)) as u32,
+            )
+        })
+        .collect();
+    draws.sort_unstable();
+    for (i, (epoch, tick)) in draws.into_iter().enumerate() {
+        // Drawn in this order so a seed keeps producing the same state
+        let winner_address = rng.id();
+        let revenue = 1 + rng.below(1_000_000_000);
+        let day_of_week = days
+            .get(rng.below(days.len().max(1) as u64) as usize)
+            .copied()
+            .unwrap_or(0);
+        new_rl.winners[i % RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY] =
+            WinnerInfoNew::new(winner_address, revenue, tick, epoch, day_of_week);
+    }
+    new_rl.winners_counter = options.winners as u64;
+
+    new_rl
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(seed: u64) -> GenOptions {
+        GenOptions {
+            output: String::new(),
+            players: 20,
+            winners: 1_030,
+            seed,
+            schedule: 0b0000_1001,
+        }
+    }
+
+    #[test]
+    fn same_seed_generates_the_same_state() {
+        let state = generate(&options(7));
+        assert_eq!(generate(&options(7)), state);
+        assert_ne!(generate(&options(8)), state);
+    }
+
+    #[test]
+    fn generated_state_is_valid() {
+        let state = generate(&options(7));
+        assert_eq!(state.validate_fees(), Ok(()));
+        assert_eq!(state.validate_schedule(), Ok(()));
+        assert_eq!(state.active_player_count(), 20);
+        assert!(state.has_wrapped());
+        assert!(state.winners_out_of_order().is_empty());
+        assert!(state
+            .winners_chronological()
+            .all(|w| matches!(w.day_of_week, 0 | 3)));
+    }
+}
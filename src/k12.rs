@@ -0,0 +1,86 @@
+//! KangarooTwelve (KT128 without customization string), the hash Qubic uses
+//! for identity checksums and content digests.
+
+use ::k12::{ExtendableOutput, Kt128, Update, XofReader};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// First `out_len` bytes of the K12 output stream of `data`.
+///
+/// K12 is an extendable-output function: a shorter output is a prefix of a
+/// longer one, so `k12_hash(data, 3)` is the start of `k12_digest(data)`.
+pub fn k12_hash(data: &[u8], out_len: usize) -> Vec<u8> {
+    let mut hasher = Kt128::default();
+    hasher.update(data);
+    let mut out = vec![0u8; out_len];
+    hasher.finalize_xof().read(&mut out);
+    out
+}
+
+/// 32-byte KangarooTwelve digest of `buf`, the hash Qubic itself uses.
+pub fn k12_digest(buf: &[u8]) -> [u8; 32] {
+    let mut hasher = K12Hasher::default();
+    hasher.update(buf);
+    hasher.finalize()
+}
+
+/// Incremental form of `k12_digest`, for data produced in pieces.
+#[derive(Default)]
+pub struct K12Hasher(Kt128);
+
+impl K12Hasher {
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    pub fn finalize(self) -> [u8; 32] {
+        let mut digest = [0u8; 32];
+        self.0.finalize_xof().read(&mut digest);
+        digest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::to_hex;
+
+    /// `ptn(n)` of the KangarooTwelve specification: `0x00..=0xFA` repeated
+    /// up to `n` bytes.
+    fn ptn(n: usize) -> Vec<u8> {
+        (0..n).map(|i| (i % 251) as u8).collect()
+    }
+
+    // Test vectors of RFC 9861, section 5 (KT128 with an empty customization string)
+    #[test]
+    fn empty_message_matches_the_spec() {
+        assert_eq!(
+            to_hex(&k12_digest(&[])),
+            "1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5"
+        );
+        assert_eq!(
+            to_hex(&k12_hash(&[], 64)),
+            "1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5\
+             4269c056b8c82e48276038b6d292966cc07a3d4645272e31ff38508139eb0a71"
+        );
+    }
+
+    #[test]
+    fn pattern_message_matches_the_spec() {
+        assert_eq!(
+            to_hex(&k12_digest(&ptn(17))),
+            "6bf75fa2239198db4772e36478f8e19b0f371205f6a9a93a273f51df37122888"
+        );
+    }
+
+    #[test]
+    fn incremental_hash_matches_the_one_shot_digest() {
+        let data = ptn(17 * 17 * 17);
+        let mut hasher = K12Hasher::default();
+        for chunk in data.chunks(1_000) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.finalize(), k12_digest(&data));
+        assert_eq!(k12_hash(&data, 3), k12_digest(&data)[..3]);
+    }
+}
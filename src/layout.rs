@@ -0,0 +1,178 @@
+//! Size comparison and field tables of the `OldRL` and `NewRL` layouts.
+//!
+//! Every number comes from `size_of`/`offset_of!`, so the breakdown follows
+//! any change to the structures.
+
+use crate::common::{Id, RL_MAX_NUMBER_OF_PLAYERS, RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY};
+use crate::new_rl::{NewRL, WinnerInfoNew, NEW_RL_SIZE};
+use crate::old_rl::{DrawGuard, OldRL, PlayerHashSet, WinnerInfo, OLD_RL_SIZE};
+use crate::StateFormat;
+use alloc::vec::Vec;
+use core::mem::{offset_of, size_of};
+
+/// Bytes one part of the state takes up in each layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionSize {
+    pub name: &'static str,
+    /// Size in the `OldRL` layout.
+    pub old: usize,
+    /// Size in the `NewRL` layout.
+    pub new: usize,
+    /// What changed between the two layouts.
+    pub note: &'static str,
+}
+
+impl RegionSize {
+    /// Growth from old to new in bytes, negative when the region shrank.
+    pub fn delta(&self) -> isize {
+        self.new as isize - self.old as isize
+    }
+}
+
+/// Growth of the whole state from `OldRL` to `NewRL` in bytes.
+pub fn size_delta() -> isize {
+    NEW_RL_SIZE as isize - OLD_RL_SIZE as isize
+}
+
+/// Winners history, player storage and the remaining fields (including
+/// padding), which together add up to the full size of each layout.
+pub fn region_sizes() -> [RegionSize; 3] {
+    let old_winners = size_of::<[WinnerInfo; RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY]>();
+    let new_winners = size_of::<[WinnerInfoNew; RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY]>();
+    let old_players = size_of::<PlayerHashSet>();
+    let new_players = size_of::<[Id; RL_MAX_NUMBER_OF_PLAYERS]>();
+    [
+        RegionSize {
+            name: "winners",
+            old: old_winners,
+            new: new_winners,
+            note: "day_of_week added to every entry",
+        },
+        RegionSize {
+            name: "players",
+            old: old_players,
+            new: new_players,
+            note: "hash set replaced by a flat id array (no occupation_flags, population, mark_removal_counter)",
+        },
+        RegionSize {
+            name: "other fields",
+            old: OLD_RL_SIZE - old_winners - old_players,
+            new: NEW_RL_SIZE - new_winners - new_players,
+            note: "next_epoch_data, player_counter, last_draw_*, schedule and draw_hour added",
+        },
+    ]
+}
+
+/// One row of a layout table: a field, or the padding up to the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSpan {
+    /// Field path, e.g. `players.population`; `None` for padding.
+    pub name: Option<&'static str>,
+    /// Byte offset from the start of the file.
+    pub offset: usize,
+    pub size: usize,
+}
+
+impl FieldSpan {
+    /// First byte after the span.
+    pub fn end(&self) -> usize {
+        self.offset + self.size
+    }
+}
+
+/// Size of the field `accessor` points at.
+fn size_of_field<T, F>(_accessor: fn(&T) -> &F) -> usize {
+    size_of::<F>()
+}
+
+/// Span of a (possibly nested) field of `$outer`, placed `$base` bytes into the file.
+macro_rules! span {
+    ($outer:ty, $base:expr, $($field:ident).+) => {
+        FieldSpan {
+            name: Some(stringify!($($field).+)),
+            offset: $base + offset_of!($outer, $($field).+),
+            size: size_of_field(|state: &$outer| &state.$($field).+),
+        }
+    };
+}
+
+/// Every field of a state file in `format`, in file order, with a padding
+/// row wherever the next field (or the end of the file) does not follow
+/// immediately.
+///
+/// Arrays are single rows; player hash set internals and the draw-guard
+/// trailer of an extended old image are listed field by field.
+pub fn field_table(format: StateFormat) -> Vec<FieldSpan> {
+    let fields = match format {
+        StateFormat::Old | StateFormat::ExtendedOld => {
+            let mut fields = alloc::vec![
+                span!(OldRL, 0, team_address),
+                span!(OldRL, 0, owner_address),
+                span!(OldRL, 0, team_fee_percent),
+                span!(OldRL, 0, distribution_fee_percent),
+                span!(OldRL, 0, winner_fee_percent),
+                span!(OldRL, 0, burn_percent),
+                span!(OldRL, 0, ticket_price),
+                span!(OldRL, 0, players.players),
+                span!(OldRL, 0, players.occupation_flags),
+                span!(OldRL, 0, players.population),
+                span!(OldRL, 0, players.mark_removal_counter),
+                span!(OldRL, 0, winners),
+                span!(OldRL, 0, winners_info_next_empty_index),
+                span!(OldRL, 0, current_state),
+            ];
+            if format == StateFormat::ExtendedOld {
+                let base = OLD_RL_SIZE;
+                fields.extend([
+                    span!(DrawGuard, base, last_draw_day),
+                    span!(DrawGuard, base, last_draw_hour),
+                    span!(DrawGuard, base, last_draw_date_stamp),
+                ]);
+            }
+            fields
+        }
+        StateFormat::New => alloc::vec![
+            span!(NewRL, 0, winners),
+            span!(NewRL, 0, players),
+            span!(NewRL, 0, team_address),
+            span!(NewRL, 0, owner_address),
+            span!(NewRL, 0, next_epoch_data.new_price),
+            span!(NewRL, 0, next_epoch_data.schedule),
+            span!(NewRL, 0, ticket_price),
+            span!(NewRL, 0, player_counter),
+            span!(NewRL, 0, winners_counter),
+            span!(NewRL, 0, last_draw_day),
+            span!(NewRL, 0, last_draw_hour),
+            span!(NewRL, 0, last_draw_date_stamp),
+            span!(NewRL, 0, team_fee_percent),
+            span!(NewRL, 0, distribution_fee_percent),
+            span!(NewRL, 0, winner_fee_percent),
+            span!(NewRL, 0, burn_percent),
+            span!(NewRL, 0, schedule),
+            span!(NewRL, 0, draw_hour),
+            span!(NewRL, 0, current_state),
+        ],
+    };
+
+    let mut table = Vec::with_capacity(fields.len() * 2);
+    let mut cursor = 0;
+    for field in fields {
+        if field.offset > cursor {
+            table.push(FieldSpan {
+                name: None,
+                offset: cursor,
+                size: field.offset - cursor,
+            });
+        }
+        cursor = field.end();
+        table.push(field);
+    }
+    if format.size() > cursor {
+        table.push(FieldSpan {
+            name: None,
+            offset: cursor,
+            size: format.size() - cursor,
+        });
+    }
+    table
+}
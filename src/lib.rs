@@ -0,0 +1,692 @@
+//! Conversion of Qubic Random Lottery contract state from the old layout
+//! (`OldRL`) to the new one (`NewRL`).
+//!
+//! The byte-level (de)serialization and the conversion itself are pure and
+//! synchronous, apart from `write_new_rl_to`, which streams an image to any
+//! async writer; file handling is left to the `rlconverter` binary.
+//!
+//! Without the default `std` feature the crate is `no_std` + `alloc`: the
+//! state structures, their byte images and the conversion remain, while
+//! gzip, base64 and the async writer are left out.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod analysis;
+pub mod checks;
+pub mod common;
+pub mod compact;
+#[cfg(feature = "serde")]
+pub mod config;
+pub mod diff;
+pub mod error;
+pub mod k12;
+pub mod layout;
+pub mod new_rl;
+pub mod old_rl;
+pub mod report;
+pub mod schedule;
+
+use crate::common::{k12_digest, Id, RL_MAX_NUMBER_OF_PLAYERS};
+use crate::error::ConvertError;
+use crate::new_rl::{NewRL, NEW_RL_SIZE};
+use crate::old_rl::{DrawGuard, OldRL, OLD_RL_SIZE};
+use crate::report::ConversionReport;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use {
+    alloc::borrow::Cow,
+    alloc::string::{String, ToString},
+    std::io::{Read, Write},
+    tokio::io::{AsyncWrite, AsyncWriteExt},
+};
+
+/// Layout of a state file, told apart by its length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateFormat {
+    /// `OldRL` image, input of the conversion
+    Old,
+    /// `OldRL` image followed by a `DrawGuard` trailer, written by an
+    /// intermediate contract build
+    ExtendedOld,
+    /// `NewRL` image, already converted
+    New,
+}
+
+impl StateFormat {
+    /// Size in bytes of a state file in this layout.
+    pub fn size(self) -> usize {
+        match self {
+            StateFormat::Old => OLD_RL_SIZE,
+            StateFormat::ExtendedOld => DrawGuard::EXTENDED_OLD_SIZE,
+            StateFormat::New => NEW_RL_SIZE,
+        }
+    }
+
+    /// Picks the layout whose size equals `len` bytes.
+    pub fn detect(len: usize) -> Result<StateFormat, ConvertError> {
+        [StateFormat::Old, StateFormat::ExtendedOld, StateFormat::New]
+            .into_iter()
+            .find(|format| format.size() == len)
+            .ok_or(ConvertError::UnrecognizedSize(len))
+    }
+}
+
+/// Bytes accepted past the largest layout, enough for the checksum trailer
+/// and for the zero padding `--allow-trailing` trims.
+pub const INPUT_SLACK: usize = 4096;
+
+/// Largest decoded input worth reading: the extended old layout, the
+/// longest one, plus `INPUT_SLACK`. Anything longer cannot be a state file.
+pub const MAX_INPUT_LEN: usize = DrawGuard::EXTENDED_OLD_SIZE + INPUT_SLACK;
+
+const _: () = assert!(NEW_RL_SIZE + CHECKSUM_LEN <= DrawGuard::EXTENDED_OLD_SIZE);
+
+/// First two bytes of every gzip stream.
+#[cfg(feature = "std")]
+pub const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Decompresses `buf` if it starts with the gzip magic bytes, otherwise
+/// returns it unchanged.
+///
+/// A raw state whose first address happens to start with the magic bytes
+/// does not decompress; it is returned unchanged when its size matches one
+/// of the layouts, so only genuinely broken gzip input is an error.
+#[cfg(feature = "std")]
+pub fn gunzip_if_compressed(buf: &[u8]) -> Result<Cow<'_, [u8]>, ConvertError> {
+    if !buf.starts_with(&GZIP_MAGIC) {
+        return Ok(Cow::Borrowed(buf));
+    }
+    // Inflate one byte past the limit, so a gzip bomb stops early
+    let mut decompressed = Vec::new();
+    let mut decoder = flate2::read::GzDecoder::new(buf).take(MAX_INPUT_LEN as u64 + 1);
+    match decoder.read_to_end(&mut decompressed) {
+        Ok(_) if decompressed.len() > MAX_INPUT_LEN => {
+            Err(ConvertError::InputTooLarge(MAX_INPUT_LEN))
+        }
+        Ok(_) => Ok(Cow::Owned(decompressed)),
+        Err(_) if StateFormat::detect(buf.len()).is_ok() => Ok(Cow::Borrowed(buf)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Compresses `buf` into a gzip stream.
+#[cfg(feature = "std")]
+pub fn gzip(buf: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    // Writing into a Vec cannot fail
+    encoder.write_all(buf).expect("in-memory gzip write");
+    encoder.finish().expect("in-memory gzip finish")
+}
+
+/// Cuts zero padding after the first `size` bytes of `buf`.
+///
+/// Storage layers may pad state files up to a page boundary. Inputs no longer
+/// than `size` are returned unchanged; a longer input is truncated to `size`
+/// bytes only if every extra byte is zero.
+pub fn trim_zero_padding(buf: &[u8], size: usize) -> Result<&[u8], ConvertError> {
+    if buf.len() <= size {
+        return Ok(buf);
+    }
+    match buf[size..].iter().position(|&b| b != 0) {
+        None => Ok(&buf[..size]),
+        Some(i) => Err(ConvertError::NonZeroTrailing {
+            expected: size,
+            offset: size + i,
+        }),
+    }
+}
+
+/// Length of the checksum trailer that can follow a NewRL image.
+pub const CHECKSUM_LEN: usize = 4;
+
+/// Checksum of a NewRL image: the first `CHECKSUM_LEN` bytes of its
+/// KangarooTwelve digest.
+pub fn new_rl_checksum(image: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let digest = k12_digest(image);
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+/// Encodes a `NewRL` like `write_new_rl_to_bytes`, followed by the
+/// checksum trailer of the image.
+pub fn write_new_rl_with_checksum(new_rl: &NewRL) -> Vec<u8> {
+    let mut bytes = write_new_rl_to_bytes(new_rl);
+    let checksum = new_rl_checksum(&bytes);
+    bytes.extend_from_slice(&checksum);
+    bytes
+}
+
+/// Verifies and removes the checksum trailer of a NewRL file that has one.
+///
+/// Only inputs exactly `CHECKSUM_LEN` bytes longer than a NewRL image carry
+/// a trailer; every other input is returned unchanged.
+pub fn strip_checksum(buf: &[u8]) -> Result<&[u8], ConvertError> {
+    if buf.len() != NEW_RL_SIZE + CHECKSUM_LEN {
+        return Ok(buf);
+    }
+    let (body, trailer) = buf.split_at(NEW_RL_SIZE);
+    let expected = new_rl_checksum(body);
+    if trailer != expected {
+        return Err(ConvertError::ChecksumMismatch {
+            expected,
+            actual: trailer.try_into().expect("trailer is CHECKSUM_LEN bytes"),
+        });
+    }
+    Ok(body)
+}
+
+/// Converts an old-layout state into the new layout.
+pub fn convert(old: &OldRL) -> NewRL {
+    NewRL::from(old)
+}
+
+/// Converts an old-layout state and describes what was migrated, defaulted
+/// and dropped along the way.
+pub fn convert_with_report(old: &OldRL) -> (NewRL, ConversionReport) {
+    let new_rl = convert(old);
+    let report = ConversionReport::new(old, &new_rl);
+    (new_rl, report)
+}
+
+/// Converts an extended old-layout state, carrying the `last_draw_*` fields
+/// of its draw-guard trailer over (see `NewRL::from_extended_old`).
+pub fn convert_with_draw_guard(old: &OldRL, guard: &DrawGuard) -> NewRL {
+    NewRL::from_extended_old(old, guard)
+}
+
+/// Checks that every `(slot, id)` pair of a source state fits into the
+/// NewRL players array, which keeps slot positions.
+///
+/// Both layouts hold `RL_MAX_NUMBER_OF_PLAYERS` players today, so this only
+/// fails for a source layout with a larger hash set.
+pub fn check_player_capacity<'a>(
+    slots: impl IntoIterator<Item = (usize, &'a Id)>,
+) -> Result<(), ConvertError> {
+    let dropped = slots
+        .into_iter()
+        .filter(|(i, _)| *i >= RL_MAX_NUMBER_OF_PLAYERS)
+        .count();
+    if dropped > 0 {
+        return Err(ConvertError::PlayerCapacity {
+            dropped,
+            capacity: RL_MAX_NUMBER_OF_PLAYERS,
+        });
+    }
+    Ok(())
+}
+
+/// Decodes an `OldRL` from the raw contents of a state file.
+pub fn read_old_rl_from_bytes(buf: &[u8]) -> Result<Box<OldRL>, ConvertError> {
+    Ok(Box::new(OldRL::from_bytes(buf)?))
+}
+
+/// Decodes an extended old state file into its `OldRL` and draw-guard trailer.
+pub fn read_extended_old_rl_from_bytes(
+    buf: &[u8],
+) -> Result<(Box<OldRL>, DrawGuard), ConvertError> {
+    let (old, guard) = OldRL::from_extended_bytes(buf)?;
+    Ok((Box::new(old), guard))
+}
+
+/// Decodes a `NewRL` from the raw contents of a state file.
+pub fn read_new_rl_from_bytes(buf: &[u8]) -> Result<Box<NewRL>, ConvertError> {
+    Ok(Box::new(NewRL::from_bytes(buf)?))
+}
+
+/// Encodes a `NewRL` into the byte image written to a state file.
+///
+/// The image is built field by field into a zeroed buffer, so padding bytes
+/// are always zero and equal states always encode to identical bytes, which
+/// keeps file hashes stable.
+pub fn write_new_rl_to_bytes(new_rl: &NewRL) -> Vec<u8> {
+    new_rl.to_bytes()
+}
+
+/// Encodes a `NewRL` in the compact format of the `compact` module: only
+/// active players and recorded winners, so positions are not kept.
+pub fn write_new_rl_compact(new_rl: &NewRL) -> Vec<u8> {
+    compact::to_compact_bytes(new_rl)
+}
+
+/// Decodes a `NewRL` written by `write_new_rl_compact`.
+pub fn read_new_rl_compact(buf: &[u8]) -> Result<Box<NewRL>, ConvertError> {
+    Ok(Box::new(compact::from_compact_bytes(buf)?))
+}
+
+/// Encodes a `NewRL` as a single standard-base64 string of its byte image.
+#[cfg(feature = "std")]
+pub fn write_new_rl_to_base64(new_rl: &NewRL) -> String {
+    use base64::Engine;
+
+    base64::engine::general_purpose::STANDARD.encode(write_new_rl_to_bytes(new_rl))
+}
+
+/// Decodes a state file given as standard-base64 text, the counterpart of
+/// `write_new_rl_to_base64`.
+///
+/// All whitespace is ignored, so trailing newlines and line-wrapped output
+/// of tools like `base64` are accepted.
+#[cfg(feature = "std")]
+pub fn decode_base64_state(text: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    use base64::Engine;
+
+    let compact: Vec<u8> = text
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    base64::engine::general_purpose::STANDARD
+        .decode(compact)
+        .map_err(|e| ConvertError::InvalidBase64(e.to_string()))
+}
+
+/// Decodes a state file given as hex text, e.g. `0xAABB...` or
+/// space-separated bytes like `aa bb cc`.
+///
+/// One leading `0x` prefix and all whitespace are ignored; upper- and
+/// lower-case digits are accepted.
+pub fn decode_hex_state(text: &[u8]) -> Result<Vec<u8>, ConvertError> {
+    let digits: Vec<u8> = text
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    let digits = digits
+        .strip_prefix(b"0x")
+        .or_else(|| digits.strip_prefix(b"0X"))
+        .unwrap_or(&digits);
+    if !digits.len().is_multiple_of(2) {
+        return Err(ConvertError::InvalidHex(format!(
+            "odd number of hex digits ({})",
+            digits.len()
+        )));
+    }
+
+    let nibble = |i: usize| {
+        let c = digits[i] as char;
+        c.to_digit(16).map(|d| d as u8).ok_or_else(|| {
+            ConvertError::InvalidHex(format!("'{}' is not a hex digit (digit {})", c, i))
+        })
+    };
+    (0..digits.len() / 2)
+        .map(|i| Ok(nibble(2 * i)? << 4 | nibble(2 * i + 1)?))
+        .collect()
+}
+
+/// Streams the byte image of a `NewRL` to `writer` one section at a time
+/// (winners, players, remaining fields) and returns the number of bytes
+/// written. The output is identical to `write_new_rl_to_bytes`.
+#[cfg(feature = "std")]
+pub async fn write_new_rl_to<W: AsyncWrite + Unpin>(
+    new_rl: &NewRL,
+    writer: &mut W,
+) -> std::io::Result<usize> {
+    let mut written = 0;
+    for section in new_rl.to_byte_sections() {
+        writer.write_all(&section).await?;
+        written += section.len();
+    }
+    Ok(written)
+}
+
+/// Encodes an `OldRL` into the byte image of a state file.
+pub fn write_old_rl_to_bytes(old_rl: &OldRL) -> Vec<u8> {
+    old_rl.to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::EState;
+    use crate::old_rl::WinnerInfo;
+
+    /// A hypothetical extended old layout whose hash set holds `extra` more
+    /// players than NewRL: the OldRL fields plus the occupied `(slot, id)`
+    /// pairs of its larger set, every slot taken.
+    struct ExtendedOld {
+        old: OldRL,
+        slots: Vec<(usize, Id)>,
+    }
+
+    impl ExtendedOld {
+        fn new(extra: usize) -> Self {
+            let slots = extended_player_slots(extra);
+            let mut players = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+            for (i, id) in slots.iter().take(RL_MAX_NUMBER_OF_PLAYERS) {
+                players[*i] = *id;
+            }
+            let old = OldRL {
+                players: old_rl::PlayerHashSet::from_players(&players),
+                ..sample_old()
+            };
+            Self { old, slots }
+        }
+
+        fn occupied_slots(&self) -> impl Iterator<Item = (usize, &Id)> {
+            self.slots.iter().map(|(i, id)| (*i, id))
+        }
+    }
+
+    /// Occupied `(slot, id)` pairs of a hash set holding `extra` more
+    /// players than NewRL, every slot taken.
+    fn extended_player_slots(extra: usize) -> Vec<(usize, Id)> {
+        (0..RL_MAX_NUMBER_OF_PLAYERS + extra)
+            .map(|i| {
+                let mut id = Id::zero();
+                id.data[..8].copy_from_slice(&(i as u64 + 1).to_le_bytes());
+                (i, id)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn player_capacity_guard_fires_for_an_oversized_source() {
+        let source = ExtendedOld::new(3);
+        assert!(matches!(
+            check_player_capacity(source.occupied_slots()),
+            Err(ConvertError::PlayerCapacity {
+                dropped: 3,
+                capacity: RL_MAX_NUMBER_OF_PLAYERS
+            })
+        ));
+        assert!(matches!(
+            NewRL::try_from_old_with_players(&source.old, source.occupied_slots()),
+            Err(ConvertError::PlayerCapacity {
+                dropped: 3,
+                capacity: RL_MAX_NUMBER_OF_PLAYERS
+            })
+        ));
+    }
+
+    #[test]
+    fn truncation_keeps_the_slots_that_fit() {
+        let source = ExtendedOld::new(3);
+        let (new_rl, dropped) = NewRL::from_old_with_players(&source.old, source.occupied_slots());
+        assert_eq!(dropped, 3);
+        assert_eq!(new_rl.active_player_count(), RL_MAX_NUMBER_OF_PLAYERS);
+        assert_eq!(new_rl.players[5], source.slots[5].1);
+        assert_eq!(new_rl.ticket_price, source.old.ticket_price);
+    }
+
+    #[test]
+    fn an_in_capacity_source_converts_like_the_old_layout() {
+        let source = ExtendedOld::new(0);
+        let new_rl =
+            NewRL::try_from_old_with_players(&source.old, source.occupied_slots()).unwrap();
+        assert_eq!(new_rl, convert(&source.old));
+    }
+
+    #[test]
+    fn player_capacity_fits_the_old_layout() {
+        let slots = extended_player_slots(0);
+        assert!(check_player_capacity(slots.iter().map(|(i, id)| (*i, id))).is_ok());
+
+        let mut players = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+        for (i, id) in &slots {
+            players[*i] = *id;
+        }
+        let old = OldRL {
+            players: old_rl::PlayerHashSet::from_players(&players),
+            ..OldRL::default()
+        };
+        assert!(check_player_capacity(old.players.occupied_slots()).is_ok());
+    }
+
+    /// A selling OldRL with one player and one winner.
+    fn sample_old() -> OldRL {
+        let mut players = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+        players[3] = Id { data: [3; 32] };
+        let mut old = OldRL {
+            team_address: Id { data: [1; 32] },
+            owner_address: Id { data: [2; 32] },
+            team_fee_percent: 10,
+            distribution_fee_percent: 20,
+            winner_fee_percent: 50,
+            burn_percent: 20,
+            ticket_price: 1_000,
+            players: old_rl::PlayerHashSet::from_players(&players),
+            winners_info_next_empty_index: 1,
+            current_state: EState::Selling,
+            ..OldRL::default()
+        };
+        old.winners[0] = WinnerInfo {
+            winner_address: Id { data: [4; 32] },
+            revenue: 900,
+            epoch: 160,
+            tick: 42,
+        };
+        old
+    }
+
+    #[test]
+    fn converts_bytes_to_bytes_without_io() {
+        let old = read_old_rl_from_bytes(&write_old_rl_to_bytes(&sample_old())).unwrap();
+        let bytes = write_new_rl_to_bytes(&convert(&old));
+        assert_eq!(bytes.len(), NEW_RL_SIZE);
+
+        let new_rl = read_new_rl_from_bytes(&bytes).unwrap();
+        assert_eq!(new_rl.team_address, old.team_address);
+        assert_eq!(new_rl.players[3], Id { data: [3; 32] });
+        assert_eq!(new_rl.winners[0].revenue, 900);
+        assert_eq!(new_rl.current_state, EState::Selling);
+    }
+
+    #[test]
+    fn format_is_detected_from_the_size() {
+        let old = write_old_rl_to_bytes(&sample_old());
+        let new = write_new_rl_to_bytes(&convert(&sample_old()));
+        assert_eq!(StateFormat::detect(old.len()).unwrap(), StateFormat::Old);
+        assert_eq!(StateFormat::detect(new.len()).unwrap(), StateFormat::New);
+        assert_eq!(
+            StateFormat::detect(DrawGuard::EXTENDED_OLD_SIZE).unwrap(),
+            StateFormat::ExtendedOld
+        );
+        assert!(matches!(
+            StateFormat::detect(old.len() - 1),
+            Err(ConvertError::UnrecognizedSize(len)) if len == OLD_RL_SIZE - 1
+        ));
+    }
+
+    #[test]
+    fn zero_padded_input_is_trimmed() {
+        let mut image = write_old_rl_to_bytes(&sample_old());
+        image.resize(OLD_RL_SIZE + 100, 0);
+        let trimmed = trim_zero_padding(&image, OLD_RL_SIZE).unwrap();
+        assert_eq!(trimmed.len(), OLD_RL_SIZE);
+        assert_eq!(read_old_rl_from_bytes(trimmed).unwrap().ticket_price, 1_000);
+    }
+
+    #[test]
+    fn garbage_padded_input_is_rejected() {
+        let mut image = write_old_rl_to_bytes(&sample_old());
+        image.resize(OLD_RL_SIZE + 100, 0);
+        image[OLD_RL_SIZE + 42] = 0x7F;
+        assert!(matches!(
+            trim_zero_padding(&image, OLD_RL_SIZE),
+            Err(ConvertError::NonZeroTrailing {
+                expected: OLD_RL_SIZE,
+                offset
+            }) if offset == OLD_RL_SIZE + 42
+        ));
+    }
+
+    #[test]
+    fn exact_input_is_not_trimmed() {
+        let image = write_old_rl_to_bytes(&sample_old());
+        assert_eq!(trim_zero_padding(&image, OLD_RL_SIZE).unwrap(), &image[..]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn gzip_wrapped_old_state_round_trips() {
+        let image = write_old_rl_to_bytes(&sample_old());
+        let compressed = gzip(&image);
+        assert!(compressed.starts_with(&GZIP_MAGIC));
+        assert!(compressed.len() < image.len());
+
+        let decompressed = gunzip_if_compressed(&compressed).unwrap();
+        assert_eq!(&decompressed[..], &image[..]);
+        let old = read_old_rl_from_bytes(&decompressed).unwrap();
+        assert_eq!(old.ticket_price, 1_000);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn uncompressed_input_is_passed_through() {
+        let image = write_old_rl_to_bytes(&sample_old());
+        assert!(matches!(
+            gunzip_if_compressed(&image).unwrap(),
+            Cow::Borrowed(bytes) if bytes == &image[..]
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[tokio::test]
+    async fn streamed_image_matches_the_byte_image() {
+        let new_rl = convert(&sample_old());
+        let mut streamed = Vec::new();
+        let written = write_new_rl_to(&new_rl, &mut streamed).await.unwrap();
+        assert_eq!(written, NEW_RL_SIZE);
+        assert_eq!(streamed, write_new_rl_to_bytes(&new_rl));
+    }
+
+    /// The core of the crate, exercised by `cargo test --lib --no-default-features`.
+    #[cfg(not(feature = "std"))]
+    #[test]
+    fn core_round_trips_without_std() {
+        let old_image = write_old_rl_to_bytes(&sample_old());
+        let old = read_old_rl_from_bytes(&old_image).unwrap();
+        let new_rl = convert(&old);
+        assert_eq!(new_rl.validate_fees(), Ok(()));
+
+        let new_image = write_new_rl_to_bytes(&new_rl);
+        assert_eq!(*read_new_rl_from_bytes(&new_image).unwrap(), new_rl);
+        assert_eq!(write_old_rl_to_bytes(&OldRL::from(&new_rl)), old_image);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn base64_wrapped_old_state_is_decoded() {
+        use base64::Engine;
+
+        let image = write_old_rl_to_bytes(&sample_old());
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&image);
+        // Wrapped at 76 columns like `base64` does, with a trailing newline
+        let mut wrapped = String::new();
+        for line in encoded.as_bytes().chunks(76) {
+            wrapped.push_str(core::str::from_utf8(line).unwrap());
+            wrapped.push('\n');
+        }
+
+        let decoded = decode_base64_state(wrapped.as_bytes()).unwrap();
+        assert_eq!(decoded, image);
+        assert_eq!(
+            read_old_rl_from_bytes(&decoded).unwrap().ticket_price,
+            1_000
+        );
+        assert!(matches!(
+            decode_base64_state(b"not base64!"),
+            Err(ConvertError::InvalidBase64(_))
+        ));
+    }
+
+    #[test]
+    fn canonical_old_size_zeroes_the_draw_guard() {
+        let image = write_old_rl_to_bytes(&sample_old());
+        assert_eq!(image.len(), OLD_RL_SIZE);
+        let new_rl = convert(&read_old_rl_from_bytes(&image).unwrap());
+        assert_eq!(new_rl.last_draw_day, 0);
+        assert_eq!(new_rl.last_draw_hour, 0);
+        assert_eq!(new_rl.last_draw_date_stamp, 0);
+    }
+
+    #[test]
+    fn extended_old_size_carries_the_draw_guard_over() {
+        let guard = DrawGuard {
+            last_draw_day: 3,
+            last_draw_hour: 12,
+            last_draw_date_stamp: 20_250_105,
+        };
+        let mut image = write_old_rl_to_bytes(&sample_old());
+        image.extend_from_slice(&guard.to_bytes());
+        assert_eq!(image.len(), DrawGuard::EXTENDED_OLD_SIZE);
+
+        let (old, read_guard) = read_extended_old_rl_from_bytes(&image).unwrap();
+        assert_eq!(read_guard, guard);
+        assert_eq!(write_old_rl_to_bytes(&old), image[..OLD_RL_SIZE]);
+        let new_rl = convert_with_draw_guard(&old, &read_guard);
+        assert_eq!(new_rl.last_draw_day, 3);
+        assert_eq!(new_rl.last_draw_hour, 12);
+        assert_eq!(new_rl.last_draw_date_stamp, 20_250_105);
+        assert_eq!(new_rl.ticket_price, 1_000);
+
+        assert!(matches!(
+            read_extended_old_rl_from_bytes(&image[..OLD_RL_SIZE]),
+            Err(ConvertError::SizeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn valid_checksum_trailer_is_stripped() {
+        let new_rl = convert(&sample_old());
+        let image = write_new_rl_with_checksum(&new_rl);
+        assert_eq!(image.len(), NEW_RL_SIZE + CHECKSUM_LEN);
+        let body = strip_checksum(&image).unwrap();
+        assert_eq!(body, &write_new_rl_to_bytes(&new_rl)[..]);
+    }
+
+    #[test]
+    fn corrupted_body_fails_the_checksum() {
+        let mut image = write_new_rl_with_checksum(&convert(&sample_old()));
+        image[100] ^= 0x01;
+        let expected = new_rl_checksum(&image[..NEW_RL_SIZE]);
+        let actual: [u8; CHECKSUM_LEN] = image[NEW_RL_SIZE..].try_into().unwrap();
+        assert!(matches!(
+            strip_checksum(&image),
+            Err(ConvertError::ChecksumMismatch { expected: e, actual: a })
+                if e == expected && a == actual
+        ));
+    }
+
+    #[test]
+    fn image_without_a_trailer_is_passed_through() {
+        let image = write_new_rl_to_bytes(&convert(&sample_old()));
+        assert_eq!(strip_checksum(&image).unwrap(), &image[..]);
+    }
+
+    #[test]
+    fn hex_with_mixed_whitespace_is_decoded() {
+        let image = write_old_rl_to_bytes(&sample_old());
+        let mut text = alloc::string::String::from("0X");
+        for (i, byte) in image.iter().enumerate() {
+            let separator = match i % 4 {
+                0 => " ",
+                1 => "\t",
+                2 => "\r\n",
+                _ => "",
+            };
+            text.push_str(&format!("{:02X}{}", byte, separator));
+        }
+        text.push('\n');
+        assert_eq!(decode_hex_state(text.as_bytes()).unwrap(), image);
+        assert_eq!(
+            decode_hex_state(b"0xaa BB\ncc").unwrap(),
+            alloc::vec![0xAA, 0xBB, 0xCC]
+        );
+    }
+
+    #[test]
+    fn malformed_hex_is_rejected() {
+        assert!(matches!(
+            decode_hex_state(b"aa b"),
+            Err(ConvertError::InvalidHex(message)) if message.contains("odd number")
+        ));
+        assert!(matches!(
+            decode_hex_state(b"aa zz"),
+            Err(ConvertError::InvalidHex(message)) if message.contains("'z'")
+        ));
+    }
+}
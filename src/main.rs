@@ -1,190 +1,2088 @@
-mod common;
-mod new_rl;
-mod old_rl;
+mod cli;
+#[cfg(feature = "gen")]
+mod gen;
 
-use crate::new_rl::NewRL;
-use old_rl::OldRL;
-use std::path::Path;
+use log::{debug, error, info, warn};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Set when stdout carries the converted binary, so human-readable text moves to stderr
+static STDOUT_IS_DATA: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--quiet`: only errors are printed
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--preview-bytes`: how many leading bytes of the input and of the
+/// written output the debug log shows
+static PREVIEW_BYTES: AtomicUsize = AtomicUsize::new(cli::DEFAULT_PREVIEW_BYTES);
+
+/// `println!` that switches to stderr while stdout is used for data, and is
+/// silenced by `--quiet`
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            if STDOUT_IS_DATA.load(Ordering::Relaxed) {
+                eprintln!($($arg)*);
+            } else {
+                println!($($arg)*);
+            }
+        }
+    };
+}
+
+use cli::{
+    Command, DiffOptions, InputEncoding, MergeOptions, Options, OutputFormat, PlayersOptions,
+    RolloverOptions, SchedulePreviewOptions, WinnersCsvOptions, WinnersForOptions,
+};
+use rlconverter::analysis::FeeSplit;
+use rlconverter::checks;
+use rlconverter::common::{
+    hexdump, hexdump_at, k12_digest, to_hex, DisplayConfig, EState, Id, K12Hasher,
+    RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY,
+};
+use rlconverter::compact::is_compact;
+use rlconverter::diff::{compare_fields, compare_scalars};
+use rlconverter::error::ConvertError;
+use rlconverter::layout;
+use rlconverter::new_rl::{NewRL, WinnerInfoNew, NEW_RL_SIZE};
+use rlconverter::old_rl::{DrawGuard, OldRL, WinnerInfo, OLD_RL_SIZE};
+use rlconverter::report::ConversionReport;
+use rlconverter::schedule::upcoming_draws;
+use rlconverter::{
+    check_player_capacity, convert, convert_with_draw_guard, decode_base64_state, decode_hex_state,
+    gunzip_if_compressed, gzip, read_extended_old_rl_from_bytes, read_new_rl_compact,
+    read_new_rl_from_bytes, read_old_rl_from_bytes, strip_checksum, trim_zero_padding,
+    write_new_rl_compact, write_new_rl_to, write_new_rl_to_base64, write_new_rl_to_bytes,
+    write_new_rl_with_checksum, write_old_rl_to_bytes, StateFormat, CHECKSUM_LEN, INPUT_SLACK,
+    MAX_INPUT_LEN,
+};
+use std::borrow::Cow;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
 use tokio::fs::File;
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+
+/// State dump rendered with the `--width` and `--redact` settings
+struct Dump<'a, T>(&'a T, DisplayConfig);
+
+impl std::fmt::Display for Dump<'_, OldRL> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt_with_config(f, &self.1)
+    }
+}
+
+impl std::fmt::Display for Dump<'_, NewRL> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt_with_config(f, &self.1)
+    }
+}
 
+/// `-` in place of a path selects stdin (input) or stdout (output)
+fn is_stdio(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// Decoded input state in whichever layout its size matched; an old state
+/// carries its draw-guard trailer when the file had one
+enum InputState {
+    Old(Box<OldRL>, Option<DrawGuard>),
+    New(Box<NewRL>),
+}
+
+/// Reads and decodes a state file, also returning the K12 digest of the raw
+/// input; with `allow_trailing`, zero padding after an OldRL image is ignored,
+/// and `encoding` tells whether the file holds the image as text
 async fn read_contract_file<P: AsRef<Path>>(
     path: P,
-) -> Result<Box<OldRL>, Box<dyn std::error::Error>> {
-    println!("📂 Opening file: {:?}", path.as_ref());
+    allow_trailing: bool,
+    encoding: InputEncoding,
+) -> Result<(InputState, [u8; 32]), ConvertError> {
+    // Base64 text is a third longer than the image it encodes, spaced hex
+    // takes up to three characters per byte
+    let limit = match encoding {
+        InputEncoding::Binary => MAX_INPUT_LEN,
+        InputEncoding::Base64 => MAX_INPUT_LEN.div_ceil(3) * 4 + INPUT_SLACK,
+        InputEncoding::Hex => MAX_INPUT_LEN * 3 + INPUT_SLACK,
+    };
 
-    let mut file = File::open(path).await?;
-    let mut buffer = Vec::new();
+    let mut buffer = if is_stdio(path.as_ref()) {
+        debug!("📖 Reading state from stdin...");
+        read_bounded(tokio::io::stdin(), limit).await?
+    } else {
+        debug!("📂 Opening file: {:?}", path.as_ref());
+        let file = File::open(path).await?;
 
-    println!("📖 Reading file contents...");
-    file.read_to_end(&mut buffer).await?;
-
-    println!("✓ Read {} bytes", buffer.len());
-    // OldRL structure size for validation
-    let expected_size = std::mem::size_of::<OldRL>();
-    println!("ℹ️  Expected OldRL structure size: {} bytes", expected_size);
-    println!("🔄 Deserializing OldRL structure...");
-
-    // Validate expected size
-    let expected = std::mem::size_of::<OldRL>();
-    if buffer.len() != expected {
-        return Err(format!(
-            "Expected size {} bytes does not match file size {} bytes",
-            expected,
-            buffer.len()
-        )
-        .into());
+        debug!("📖 Reading file contents...");
+        read_bounded(file, limit).await?
+    };
+
+    debug!("✓ Read {} bytes", buffer.len());
+    let digest = k12_digest(&buffer);
+
+    match encoding {
+        InputEncoding::Binary => {}
+        InputEncoding::Base64 => {
+            buffer = decode_base64_state(&buffer)?;
+            debug!("✓ Decoded base64 input: {} bytes", buffer.len());
+        }
+        InputEncoding::Hex => {
+            buffer = decode_hex_state(&buffer)?;
+            debug!("✓ Decoded hex input: {} bytes", buffer.len());
+        }
+    }
+
+    // Archived snapshots may be gzip-compressed; sizes are checked after inflating
+    let buffer = match gunzip_if_compressed(&buffer)? {
+        Cow::Owned(decompressed) => {
+            debug!(
+                "✓ Decompressed gzip input: {} -> {} bytes",
+                buffer.len(),
+                decompressed.len()
+            );
+            decompressed
+        }
+        Cow::Borrowed(_) => buffer,
+    };
+    debug!(
+        "ℹ️  Expected OldRL structure size: {} bytes (NewRL: {} bytes)",
+        StateFormat::Old.size(),
+        StateFormat::New.size()
+    );
+
+    // A --compact-binary image has no fixed size; only its magic identifies it
+    if is_compact(&buffer) && StateFormat::detect(buffer.len()).is_err() {
+        info!("ℹ️  Input is a compact NewRL state, the file is already converted");
+        return Ok((InputState::New(read_new_rl_compact(&buffer)?), digest));
     }
 
-    // Create uninitialized buffer for OldRL and copy bytes
-    let mut boxed: Box<OldRL> = unsafe { Box::new(std::mem::MaybeUninit::zeroed().assume_init()) };
-    unsafe {
-        std::ptr::copy_nonoverlapping(
-            buffer.as_ptr(),
-            boxed.as_mut() as *mut OldRL as *mut u8,
-            expected,
+    // A NewRL written with --with-checksum carries a trailer to verify first
+    let has_checksum = buffer.len() == StateFormat::New.size() + CHECKSUM_LEN;
+    let mut buffer = strip_checksum(&buffer)?;
+    if has_checksum {
+        info!("✓ NewRL checksum trailer verified");
+    }
+    // An extended old image is longer than OldRL but is not padding
+    if allow_trailing
+        && buffer.len() > StateFormat::Old.size()
+        && StateFormat::detect(buffer.len()).is_err()
+    {
+        let padded_len = buffer.len();
+        buffer = trim_zero_padding(buffer, StateFormat::Old.size())?;
+        info!(
+            "ℹ️  Ignoring {} bytes of zero padding",
+            padded_len - buffer.len()
         );
     }
 
-    println!("✓ Byte-by-byte structure loading successful!\n");
+    // The two layouts differ in size, so the length tells them apart
+    let format = match StateFormat::detect(buffer.len()) {
+        Ok(format) => format,
+        Err(e) => {
+            print_size_mismatch_dump(buffer, StateFormat::Old.size());
+            return Err(e);
+        }
+    };
 
-    Ok(boxed)
+    debug!("   {}", byte_preview("file", buffer));
+
+    // Decode field by field; invalid state bytes become errors
+    let state = match format {
+        StateFormat::Old => {
+            debug!("🔄 Deserializing OldRL structure...");
+            InputState::Old(read_old_rl_from_bytes(buffer)?, None)
+        }
+        StateFormat::ExtendedOld => {
+            info!("ℹ️  Input is an OldRL followed by a draw-guard trailer");
+            debug!("🔄 Deserializing OldRL structure and draw guard...");
+            let (old_rl, guard) = read_extended_old_rl_from_bytes(buffer)?;
+            InputState::Old(old_rl, Some(guard))
+        }
+        StateFormat::New => {
+            info!("ℹ️  Input size matches NewRL, the file is already converted");
+            debug!("🔄 Deserializing NewRL structure...");
+            InputState::New(read_new_rl_from_bytes(buffer)?)
+        }
+    };
+
+    debug!("✓ Byte-by-byte structure loading successful!");
+
+    Ok((state, digest))
+}
+
+/// Reads `reader` to its end, but never more than `limit` bytes: a device
+/// or a runaway pipe fails as soon as it has produced one byte too many.
+///
+/// Pipes and slow sources hand out data in pieces, so this loops until EOF
+/// and retries reads that were interrupted; an input that ends early comes
+/// back short and fails the size check of the caller.
+async fn read_bounded<R: AsyncRead + Unpin>(
+    reader: R,
+    limit: usize,
+) -> Result<Vec<u8>, ConvertError> {
+    let mut reader = reader.take(limit as u64 + 1);
+    let mut buffer = Vec::new();
+    loop {
+        match reader.read_buf(&mut buffer).await {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    if buffer.len() > limit {
+        return Err(ConvertError::InputTooLarge(limit));
+    }
+    Ok(buffer)
+}
+
+/// Hex preview of the first `--preview-bytes` bytes of `bytes`, clamped to
+/// its length, e.g. "First 4 bytes of file: 1f 8b 08 00"
+fn byte_preview(what: &str, bytes: &[u8]) -> String {
+    let shown = &bytes[..bytes.len().min(PREVIEW_BYTES.load(Ordering::Relaxed))];
+    let hex: Vec<String> = shown.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("First {} bytes of {}: {}", shown.len(), what, hex.join(" "))
 }
 
-/// Asynchronously saves NewRL to a binary file
+/// Prints the byte delta and head/tail hex dumps of a wrongly sized input
+fn print_size_mismatch_dump(buffer: &[u8], expected: usize) {
+    let delta = buffer.len() as i64 - expected as i64;
+    warn!(
+        "⚠️  File is {} bytes {} than expected ({} vs {} bytes)",
+        delta.unsigned_abs(),
+        if delta > 0 { "longer" } else { "shorter" },
+        buffer.len(),
+        expected
+    );
+
+    let head = &buffer[..buffer.len().min(64)];
+    warn!("   First {} bytes:\n{}", head.len(), hexdump(head));
+    if buffer.len() > 64 {
+        let tail_start = buffer.len() - buffer.len().min(64);
+        warn!(
+            "   Last {} bytes:\n{}",
+            buffer.len() - tail_start,
+            hexdump_at(&buffer[tail_start..], tail_start)
+        );
+    }
+}
+
+/// Writes `new_rl` in the requested encoding, returning the K12 digest and
+/// length of the bytes written.
+///
+/// A file is written to a `<path>.tmp` sibling first and only renamed over
+/// `path` once it is complete and synced, so a killed run never leaves a
+/// truncated output behind.
 async fn write_new_rl_to_file<P: AsRef<Path>>(
     path: P,
     new_rl: &NewRL,
-) -> Result<(), Box<dyn std::error::Error>> {
+    format: OutputFormat,
+    gzip_out: bool,
+    with_checksum: bool,
+) -> Result<([u8; 32], usize), ConvertError> {
+    let path = path.as_ref();
+    info!("💾 Saving NewRL to file: {:?}", path);
+
+    if is_stdio(path) {
+        return write_new_rl_encoded(
+            &mut tokio::io::stdout(),
+            new_rl,
+            format,
+            gzip_out,
+            with_checksum,
+        )
+        .await;
+    }
+
+    let temp = temp_path(path);
+    debug!("   Writing to temporary file {:?}", temp);
+    let result = async {
+        let mut file = File::create(&temp).await?;
+        let written =
+            write_new_rl_encoded(&mut file, new_rl, format, gzip_out, with_checksum).await?;
+        file.sync_all().await?;
+        Ok(written)
+    }
+    .await;
+    let written = match result {
+        Ok(written) => written,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return Err(e);
+        }
+    };
+    if let Err(e) = replace_with_temp(&temp, path).await {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return Err(e);
+    }
+
+    Ok(written)
+}
+
+/// `<path>.tmp`, where an output is written before it replaces `path`
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+/// Renames the finished `temp` file over `path`, falling back to a copy when
+/// the two are on different filesystems
+async fn replace_with_temp(temp: &Path, path: &Path) -> Result<(), ConvertError> {
+    match tokio::fs::rename(temp, path).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            debug!("   Rename crosses filesystems, copying instead");
+            tokio::fs::copy(temp, path).await?;
+            tokio::fs::remove_file(temp).await?;
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Encodes `new_rl` into `sink`, returning the K12 digest and length of the
+/// bytes written
+async fn write_new_rl_encoded<W: AsyncWrite + Unpin>(
+    sink: &mut W,
+    new_rl: &NewRL,
+    format: OutputFormat,
+    gzip_out: bool,
+    with_checksum: bool,
+) -> Result<([u8; 32], usize), ConvertError> {
     use tokio::io::AsyncWriteExt;
 
-    println!("\n💾 Saving NewRL to file: {:?}", path.as_ref());
+    let (digest, size) = if format == OutputFormat::Binary && !gzip_out {
+        debug!("   NewRL structure size: {} bytes", NEW_RL_SIZE);
 
-    let size = std::mem::size_of::<NewRL>();
-    println!("   NewRL structure size: {} bytes", size);
+        if log::log_enabled!(log::Level::Debug) {
+            debug!(
+                "   {}",
+                byte_preview("output", &write_new_rl_to_bytes(new_rl))
+            );
+        }
 
-    // Create buffer and copy structure bytes
-    let bytes = unsafe { std::slice::from_raw_parts(new_rl as *const NewRL as *const u8, size) };
+        // Stream the raw image section by section, hashing on the way
+        let mut writer = DigestWriter::new(&mut *sink);
+        let mut size = write_new_rl_to(new_rl, &mut writer).await?;
+        if with_checksum {
+            // content_hash is the K12 digest of exactly the image just streamed
+            writer
+                .write_all(&new_rl.content_hash()[..CHECKSUM_LEN])
+                .await?;
+            size += CHECKSUM_LEN;
+        }
+        (writer.finalize(), size)
+    } else {
+        let bytes: Vec<u8> = match format {
+            OutputFormat::Binary if with_checksum => write_new_rl_with_checksum(new_rl),
+            OutputFormat::Binary => write_new_rl_to_bytes(new_rl),
+            #[cfg(feature = "serde")]
+            OutputFormat::Json => serde_json::to_string_pretty(new_rl)?.into_bytes(),
+            #[cfg(feature = "serde")]
+            OutputFormat::JsonCompact => {
+                let mut line = serde_json::to_vec(new_rl)?;
+                line.push(b'\n');
+                line
+            }
+            OutputFormat::CompactBinary => write_new_rl_compact(new_rl),
+            OutputFormat::Base64 => {
+                let mut line = write_new_rl_to_base64(new_rl);
+                line.push('\n');
+                line.into_bytes()
+            }
+        };
+        let bytes = if gzip_out { gzip(&bytes) } else { bytes };
+        debug!("   {}", byte_preview("output", &bytes));
+        sink.write_all(&bytes).await?;
+        (k12_digest(&bytes), bytes.len())
+    };
+    sink.flush().await?;
 
-    let mut file = File::create(path).await?;
-    file.write_all(bytes).await?;
-    file.flush().await?;
+    info!("✓ File successfully written ({} bytes)", size);
 
-    println!("✓ File successfully written ({} bytes)", size);
+    Ok((digest, size))
+}
 
+/// Passes writes through to `inner` while hashing every accepted byte
+struct DigestWriter<W> {
+    inner: W,
+    hasher: K12Hasher,
+}
+
+impl<W> DigestWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: K12Hasher::default(),
+        }
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        self.hasher.finalize()
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for DigestWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = &mut *self;
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = &poll {
+            this.hasher.update(&buf[..*written]);
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Writes a conversion report as pretty-printed JSON
+#[cfg(feature = "serde")]
+async fn write_report(path: &Path, report: &ConversionReport) -> Result<(), ConvertError> {
+    let mut json = serde_json::to_string_pretty(report)?;
+    json.push('\n');
+    tokio::fs::write(path, json).await?;
+    info!("📝 Conversion report written to {:?}", path);
     Ok(())
 }
 
-async fn read_new_rl_file<P: AsRef<Path>>(
-    path: P,
-) -> Result<Box<NewRL>, Box<dyn std::error::Error>> {
-    println!("📂 Opening NewRL file: {:?}", path.as_ref());
+/// `--report` is rejected at parse time without the `serde` feature
+#[cfg(not(feature = "serde"))]
+async fn write_report(_path: &Path, _report: &ConversionReport) -> Result<(), ConvertError> {
+    unreachable!("--report requires the serde feature")
+}
 
-    let mut file = File::open(path).await?;
-    let mut buffer = Vec::new();
+/// Appends one `{input, input_hash, output, output_hash, size}` record to the
+/// JSON array in `manifest`, creating the file if needed
+#[cfg(feature = "serde")]
+async fn append_manifest(
+    manifest: &Path,
+    input: &Path,
+    input_digest: &[u8; 32],
+    output: &Path,
+    output_digest: &[u8; 32],
+    size: usize,
+) -> Result<(), ConvertError> {
+    let mut records: Vec<serde_json::Value> = match tokio::fs::read(manifest).await {
+        Ok(existing) => serde_json::from_slice(&existing)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    records.push(serde_json::json!({
+        "input": input.display().to_string(),
+        "input_hash": to_hex(input_digest),
+        "output": output.display().to_string(),
+        "output_hash": to_hex(output_digest),
+        "size": size,
+    }));
+
+    let mut json = serde_json::to_string_pretty(&records)?;
+    json.push('\n');
+    tokio::fs::write(manifest, json).await?;
+    info!("📝 Manifest record appended to {:?}", manifest);
+    Ok(())
+}
+
+/// `--manifest` is rejected at parse time without the `serde` feature
+#[cfg(not(feature = "serde"))]
+async fn append_manifest(
+    _manifest: &Path,
+    _input: &Path,
+    _input_digest: &[u8; 32],
+    _output: &Path,
+    _output_digest: &[u8; 32],
+    _size: usize,
+) -> Result<(), ConvertError> {
+    unreachable!("--manifest requires the serde feature")
+}
+
+async fn read_new_rl_file<P: AsRef<Path>>(path: P) -> Result<Box<NewRL>, ConvertError> {
+    debug!("📂 Opening NewRL file: {:?}", path.as_ref());
+
+    let file = File::open(path).await?;
 
-    println!("📖 Reading file contents...");
-    file.read_to_end(&mut buffer).await?;
+    debug!("📖 Reading file contents...");
+    let buffer = read_bounded(file, MAX_INPUT_LEN).await?;
 
-    let expected = std::mem::size_of::<NewRL>();
-    println!(
+    let expected = NEW_RL_SIZE;
+    debug!(
         "✓ Read {} bytes (expected {} bytes)",
         buffer.len(),
         expected
     );
 
-    if buffer.len() != expected {
-        return Err(format!(
-            "Expected size {} bytes does not match file size {} bytes",
-            expected,
-            buffer.len()
+    let buffer = gunzip_if_compressed(&buffer)?;
+    let boxed = if is_compact(&buffer) && StateFormat::detect(buffer.len()).is_err() {
+        read_new_rl_compact(&buffer)?
+    } else {
+        read_new_rl_from_bytes(strip_checksum(&buffer)?)?
+    };
+
+    debug!("✓ Byte-by-byte NewRL loading successful!");
+
+    Ok(boxed)
+}
+
+/// Converts a decoded OldRL, warning about anything the old layout cannot carry over;
+/// the `last_draw_*` fields come from `guard` when the input had a draw-guard trailer
+fn convert_old_rl(
+    rl_state: &OldRL,
+    guard: Option<&DrawGuard>,
+    options: &Options,
+) -> Result<NewRL, ConvertError> {
+    // Players without a NewRL slot are only dropped when asked to
+    match check_player_capacity(rl_state.players.occupied_slots()) {
+        Ok(()) => {}
+        Err(ConvertError::PlayerCapacity { dropped, capacity }) if options.truncate => {
+            warn!(
+                "⚠️  Warning: --truncate drops {} player(s) past the {} NewRL player slots, their tickets are lost!",
+                dropped, capacity
+            );
+        }
+        Err(e) => return Err(e),
+    }
+
+    // Corrupt occupation flags do not stop the conversion, but deserve attention
+    if let Err(e) = rl_state.players.verify() {
+        warn!("⚠️  {}", e);
+    }
+    let duplicates = rl_state.players.find_duplicates();
+    if !duplicates.is_empty() {
+        warn!(
+            "⚠️  Warning: {} player(s) occupy more than one slot:",
+            duplicates.len()
+        );
+        for id in &duplicates {
+            warn!("   {}", options.display_config().id("player", id));
+        }
+    }
+
+    // The old layout has no draw times, so one warning covers every winner
+    let winners = rl_state
+        .winners
+        .iter()
+        .filter(|w| !w.winner_address.is_zero())
+        .count();
+    if winners > 0 {
+        warn!(
+            "⚠️  Warning: the old layout does not record draw days, keeping day of week 0 for {} winner(s)",
+            winners
+        );
+    }
+
+    let new_rl = match guard {
+        Some(guard) => convert_with_draw_guard(rl_state, guard),
+        None => convert(rl_state),
+    };
+    if let Some(warning) = wrap_warning(&new_rl) {
+        warn!("{}", warning);
+    }
+    Ok(new_rl)
+}
+
+/// Warning listing winner slots whose address and revenue contradict each other, if any
+fn inconsistent_winners_warning(new_rl: &NewRL) -> Option<String> {
+    let inconsistent = new_rl.inconsistent_winner_slots();
+    (!inconsistent.is_empty()).then(|| {
+        format!(
+            "⚠️  Warning: winner slot(s) {:?} have an address without revenue or revenue without an address",
+            inconsistent
         )
-        .into());
+    })
+}
+
+/// Warning that `player_counter` disagrees with the populated player slots, if it does
+fn player_counter_warning(new_rl: &NewRL) -> Option<String> {
+    let active_players = new_rl.active_player_count();
+    (new_rl.player_counter != active_players as u64).then(|| {
+        format!(
+            "⚠️  Warning: player_counter is {} but {} player slot(s) are populated",
+            new_rl.player_counter, active_players
+        )
+    })
+}
+
+/// Warning that the winners history of a converted state is truncated, if it wrapped
+fn wrap_warning(new_rl: &NewRL) -> Option<String> {
+    // An implausible counter gets its own warning instead
+    (new_rl.has_wrapped() && new_rl.validate_winners_counter().is_ok()).then(|| {
+        format!(
+            "⚠️  Warning: winners history has wrapped ({} winners recorded), only the last {} are kept",
+            new_rl.winners_counter, RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY
+        )
+    })
+}
+
+/// Prints how the state size changed, broken down by region with `explain`
+fn print_layout_delta(explain: bool) {
+    say!(
+        "📐 OldRL {} bytes → NewRL {} bytes ({:+} bytes)",
+        OLD_RL_SIZE,
+        NEW_RL_SIZE,
+        layout::size_delta()
+    );
+    if !explain {
+        return;
+    }
+    for region in layout::region_sizes() {
+        say!(
+            "   {:<13} {:>6} → {:>6} bytes ({:+}): {}",
+            region.name,
+            region.old,
+            region.new,
+            region.delta(),
+            region.note
+        );
+    }
+    let (old_winner, new_winner) = (size_of::<WinnerInfo>(), size_of::<WinnerInfoNew>());
+    say!(
+        "   per winner    {:>6} → {:>6} bytes{}",
+        old_winner,
+        new_winner,
+        if old_winner == new_winner {
+            ", day_of_week fits into the former padding"
+        } else {
+            ""
+        }
+    );
+}
+
+/// Prints the offset, size and name of every field of each layout, with
+/// padding gaps, for reading hex dumps
+fn print_field_tables(formats: &[StateFormat]) {
+    for &format in formats {
+        let title = match format {
+            StateFormat::Old => "OldRL",
+            StateFormat::ExtendedOld => "OldRL with draw-guard trailer",
+            StateFormat::New => "NewRL",
+        };
+        say!("📐 {} layout ({} bytes)\n", title, format.size());
+        say!("   {:>7}  {:>7}  {:>6}  field", "offset", "hex", "size");
+        for span in layout::field_table(format) {
+            say!(
+                "   {:>7}  {:#07x}  {:>6}  {}",
+                span.offset,
+                span.offset,
+                span.size,
+                span.name.unwrap_or("(padding)")
+            );
+        }
+        say!();
+    }
+}
+
+/// How an existing output file is dealt with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OverwriteDecision {
+    Overwrite,
+    Prompt,
+    Refuse,
+}
+
+/// Decides whether an existing output may be replaced.
+///
+/// `--force` always overwrites. On a terminal the user is asked; without
+/// one the file is overwritten as before, except under CI, where `--force`
+/// is required.
+fn overwrite_decision(is_tty: bool, ci: bool, force: bool) -> OverwriteDecision {
+    if force {
+        OverwriteDecision::Overwrite
+    } else if is_tty {
+        OverwriteDecision::Prompt
+    } else if ci {
+        OverwriteDecision::Refuse
+    } else {
+        OverwriteDecision::Overwrite
     }
+}
+
+/// Asks on the terminal whether an existing `output` may be replaced.
+///
+/// The prompt needs stdout to be a terminal and the answer is read from
+/// stdin, so a piped stdout or a state read from stdin never prompts. CI is
+/// detected by the `CI` environment variable.
+fn confirm_overwrite(input: &Path, output: &Path, force: bool) -> Result<(), ConvertError> {
+    use std::io::{BufRead, IsTerminal, Write};
 
-    // Create uninitialized buffer for NewRL and copy bytes
-    let mut boxed: Box<NewRL> = unsafe { Box::new(std::mem::MaybeUninit::zeroed().assume_init()) };
-    unsafe {
-        std::ptr::copy_nonoverlapping(
-            buffer.as_ptr(),
-            boxed.as_mut() as *mut NewRL as *mut u8,
-            expected,
+    let refuse = || ConvertError::OutputExists(output.display().to_string());
+    let is_tty =
+        !is_stdio(input) && std::io::stdout().is_terminal() && std::io::stdin().is_terminal();
+    match overwrite_decision(is_tty, std::env::var_os("CI").is_some(), force) {
+        OverwriteDecision::Overwrite => return Ok(()),
+        OverwriteDecision::Refuse => return Err(refuse()),
+        OverwriteDecision::Prompt => {}
+    }
+
+    eprint!("Overwrite {}? [y/N] ", output.display());
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    match answer.trim() {
+        "y" | "Y" | "yes" => Ok(()),
+        _ => Err(refuse()),
+    }
+}
+
+/// Reads, converts, validates and writes a single state file
+async fn convert_file(input: &Path, output: &Path, options: &Options) -> Result<(), ConvertError> {
+    info!("📥 Input file:  {}", input.display());
+    info!("📤 Output file: {}", output.display());
+
+    // Read the input; an already converted NewRL only passes through with --force
+    let (input_state, input_digest) =
+        read_contract_file(input, options.allow_trailing, options.input_encoding).await?;
+    let (mut new_rl, old_rl): (NewRL, Option<Box<OldRL>>) = match input_state {
+        InputState::Old(rl_state, guard) => {
+            say!("{}", Dump(rl_state.as_ref(), options.display_config()));
+            if let Some(guard) = &guard {
+                say!(
+                    "🛡️  Draw guard: last draw on day {} at {}:00, date stamp {}",
+                    guard.last_draw_day,
+                    guard.last_draw_hour,
+                    guard.last_draw_date_stamp
+                );
+            }
+            (
+                convert_old_rl(&rl_state, guard.as_ref(), options)?,
+                Some(rl_state),
+            )
+        }
+        InputState::New(new_rl) if options.force => {
+            say!(
+                "ℹ️  '{}' is already a NewRL state, passing it through",
+                input.display()
+            );
+            (*new_rl, None)
+        }
+        InputState::New(_) => return Err(ConvertError::AlreadyConverted),
+    };
+
+    // Fill in new-only fields given on the command line or in --config
+    options.overrides_for(input).apply_to(&mut new_rl);
+    if let Some(state) = options.state {
+        info!(
+            "ℹ️  Forcing current state {} (was {})",
+            state.as_str(),
+            new_rl.current_state.as_str()
         );
+        new_rl.current_state = state;
     }
 
-    println!("✓ Byte-by-byte NewRL loading successful!\n");
+    if let Some(warning) = player_counter_warning(&new_rl) {
+        warn!("{}", warning);
+    }
 
-    Ok(boxed)
+    // A history out of draw order makes every chronological view wrong
+    let out_of_order = new_rl.winners_out_of_order();
+    if !out_of_order.is_empty() {
+        if options.sort_winners {
+            let counter = new_rl.winners_counter;
+            new_rl.sort_winners();
+            say!(
+                "🔃 Sorted the winners history by epoch and tick, winners_counter {} → {}",
+                counter,
+                new_rl.winners_counter
+            );
+        } else {
+            warn!(
+                "⚠️  Warning: winner slot(s) {:?} are older than the entry before them in ring order (use --sort-winners to reorder)",
+                out_of_order
+            );
+        }
+    }
+
+    if let Some(warning) = inconsistent_winners_warning(&new_rl) {
+        warn!("{}", warning);
+    }
+
+    // Refuse to produce a state whose fee split or schedule is incoherent
+    new_rl.validate_fees()?;
+    new_rl.validate_schedule()?;
+
+    // Contradictory state/price/player combinations only fail under --strict
+    if let Err(e) = new_rl.validate_state() {
+        if options.strict {
+            return Err(e.into());
+        }
+        warn!("⚠️  Warning: {}", e);
+    }
+    if let Err(e) = new_rl.validate_winners_counter() {
+        if options.strict {
+            return Err(e.into());
+        }
+        warn!("⚠️  Warning: {}", e);
+    }
+    if let Err(e) = new_rl.validate_addresses() {
+        if options.strict {
+            return Err(e.into());
+        }
+        warn!("⚠️  Warning: {}", e);
+    }
+    for name in new_rl.zero_addresses_while_selling() {
+        warn!(
+            "⚠️  Warning: contract is Selling but {} is the zero address",
+            name
+        );
+    }
+
+    // Detailed output of NewRL
+    say!("{}", Dump(&new_rl, options.display_config()));
+    if old_rl.is_some() {
+        print_layout_delta(options.explain_layout);
+    }
+
+    if let Some(report_path) = &options.report {
+        match &old_rl {
+            Some(old_rl) => {
+                let report = ConversionReport::new(old_rl, &new_rl);
+                write_report(Path::new(report_path), &report).await?;
+            }
+            None => warn!("⚠️  Warning: input was not converted, no report written"),
+        }
+    }
+
+    if options.dry_run {
+        say!("\n🔐 Input K12:  {}", to_hex(&input_digest));
+        say!(
+            "\n✅ Dry run completed successfully, '{}' was not written",
+            output.display()
+        );
+        return Ok(());
+    }
+
+    if !is_stdio(output) && output.exists() {
+        confirm_overwrite(input, output, options.force)?;
+    }
+
+    // Save NewRL to file
+    let (output_digest, output_size) = write_new_rl_to_file(
+        output,
+        &new_rl,
+        options.format,
+        options.gzip_out,
+        options.with_checksum,
+    )
+    .await?;
+    say!("\n✅ NewRL successfully saved to '{}'", output.display());
+
+    // JSON output cannot be loaded back as a NewRL, nor can stdout be re-read
+    let reloadable = matches!(
+        options.format,
+        OutputFormat::Binary | OutputFormat::CompactBinary
+    );
+    if reloadable && !is_stdio(output) {
+        // Read back the saved NewRL for verification
+        info!("📂 Re-opening saved NewRL file for verification...");
+        let loaded_new = read_new_rl_file(output).await?;
+        debug!("{}", loaded_new);
+
+        // Compare structures
+        info!("🔍 Comparing saved NewRL structure with original...");
+        // The compact form packs the slots, so only its content must match
+        let matches = if options.format == OutputFormat::CompactBinary {
+            write_new_rl_compact(&new_rl) == write_new_rl_compact(&loaded_new)
+        } else {
+            new_rl == *loaded_new
+        };
+        if matches {
+            info!("✓ Verification successful: structures match!");
+        } else {
+            error!("❌ Verification failed: structures do not match.");
+            error!("--- ORIGINAL NewRL ---\n{:?}", new_rl);
+            error!("--- LOADED  NewRL ---\n{:?}", loaded_new);
+            return Err(ConvertError::VerificationFailed);
+        }
+    }
+
+    // Fingerprints tying this output to this input, for the audit trail
+    say!("\n🔐 Input K12:  {}", to_hex(&input_digest));
+    say!("🔐 Output K12: {}", to_hex(&output_digest));
+    if let Some(manifest) = &options.manifest {
+        append_manifest(
+            Path::new(manifest),
+            input,
+            &input_digest,
+            output,
+            &output_digest,
+            output_size,
+        )
+        .await?;
+    }
+
+    Ok(())
 }
 
-/// Prints program usage help
-fn print_usage(program_name: &str) {
-    eprintln!("Usage: {} <input_file> <output_file>", program_name);
-    eprintln!();
-    eprintln!("Arguments:");
-    eprintln!("  <input_file>   Path to the OldRL state file for reading");
-    eprintln!("  <output_file>  Path to the file for saving NewRL");
-    eprintln!();
-    eprintln!("Example:");
-    eprintln!("  {} contract0016.185 contract0016_new.185", program_name);
+/// Matches the `contract*.*` naming of state snapshots, skipping our own `_new` outputs
+fn is_contract_file(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    name.starts_with("contract") && name.contains('.') && !stem.ends_with("_new")
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("\n🎰 Random Lottery Contract - State Converter\n");
+/// Output path for a batch input: `<stem>_new.<ext>` next to it or in `output_dir`
+fn batch_output_path(input: &Path, output_dir: Option<&Path>) -> PathBuf {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{}_new", stem);
+    if let Some(ext) = input.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    match output_dir {
+        Some(dir) => dir.join(name),
+        None => input.with_file_name(name),
+    }
+}
 
-    // Parse command line arguments
-    let args: Vec<String> = std::env::args().collect();
+/// Converts every contract file in `dir`, continuing past individual failures
+async fn run_batch(dir: &Path, options: &Options) -> Result<(), ConvertError> {
+    let output_dir = options.output_dir.as_ref().map(PathBuf::from);
+    if let Some(output_dir) = &output_dir {
+        if !options.dry_run {
+            tokio::fs::create_dir_all(output_dir).await?;
+        }
+    }
 
-    if args.len() != 3 {
-        eprintln!("❌ Error: incorrect number of arguments\n");
-        print_usage(&args[0]);
-        std::process::exit(1);
+    let mut inputs = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.is_file() && is_contract_file(&path) {
+            inputs.push(path);
+        }
     }
+    inputs.sort();
 
-    let input_file = &args[1];
-    let output_file = &args[2];
+    info!(
+        "📁 Batch converting {} file(s) in {}",
+        inputs.len(),
+        dir.display()
+    );
 
-    println!("📥 Input file:  {}", input_file);
-    println!("📤 Output file: {}\n", output_file);
+    // Collisions are checked on resolved paths so `{dir}/./x` and `{dir}/x` match
+    let resolved_inputs: Vec<PathBuf> = inputs.iter().map(|input| resolve_path(input)).collect();
+    let mut outputs = Vec::with_capacity(inputs.len());
+    let mut resolved_outputs: Vec<PathBuf> = Vec::with_capacity(inputs.len());
+    for input in &inputs {
+        let output = match &options.output_template {
+            // Placeholders were checked while parsing, so expansion cannot fail here
+            Some(template) => cli::expand_output_template(template, input)
+                .expect("--output-template is validated by the parser"),
+            None => batch_output_path(input, output_dir.as_deref()),
+        };
+        let resolved = resolve_path(&output);
+        // Writing over an input would destroy an OldRL before it is converted
+        if !options.force {
+            if let Some(j) = resolved_inputs.iter().position(|other| *other == resolved) {
+                return Err(ConvertError::OutputIsInput {
+                    output: output.display().to_string(),
+                    input: inputs[j].display().to_string(),
+                });
+            }
+        }
+        // Two inputs writing the same file would silently lose one of them
+        if let Some(j) = resolved_outputs.iter().position(|other| *other == resolved) {
+            return Err(ConvertError::DuplicateOutput {
+                output: output.display().to_string(),
+                first: inputs[j].display().to_string(),
+                second: input.display().to_string(),
+            });
+        }
+        outputs.push(output);
+        resolved_outputs.push(resolved);
+    }
 
-    // Check input file existence
-    if !Path::new(input_file).exists() {
-        eprintln!("❌ Error: input file '{}' not found", input_file);
+    let mut failures = Vec::new();
+    let mut skipped = 0;
+    for (i, (input, output)) in inputs.iter().zip(&outputs).enumerate() {
+        say!(
+            "\n📦 [{}/{}] {}",
+            i + 1,
+            inputs.len(),
+            input.file_name().unwrap_or_default().to_string_lossy()
+        );
+        if options.output_template.is_some() && !options.dry_run {
+            if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+        }
+        match convert_file(input, output, options).await {
+            Ok(()) => {}
+            // Nothing to do for a state that is already NewRL
+            Err(ConvertError::AlreadyConverted) => {
+                warn!(
+                    "⚠️  Skipping '{}': already a NewRL state (use --force to pass it through)",
+                    input.display()
+                );
+                skipped += 1;
+            }
+            Err(e) => {
+                error!("❌ Error converting '{}': {}", input.display(), e);
+                failures.push((input, e));
+            }
+        }
+    }
+
+    say!("\n📊 Batch summary:");
+    say!("  Converted: {}", inputs.len() - failures.len() - skipped);
+    say!("  Skipped:   {}", skipped);
+    say!("  Failed:    {}", failures.len());
+    for (input, e) in &failures {
+        say!("    {}: {}", input.display(), e);
+    }
+
+    if !failures.is_empty() {
         std::process::exit(1);
     }
 
-    // Read OldRL
-    let rl_state = read_contract_file(input_file).await?;
-    println!("{}", rl_state);
+    say!("\n✅ Batch conversion completed successfully!");
 
-    // Convert state to NewRL
-    let new_rl: NewRL = NewRL::from(rl_state.as_ref());
+    Ok(())
+}
 
-    // Detailed output of NewRL
-    println!("{}", new_rl);
+/// Prints the field-by-field differences between two NewRL files
+async fn run_diff(options: &DiffOptions) -> Result<(), ConvertError> {
+    let left = read_new_rl_file(&options.left).await?;
+    let right = read_new_rl_file(&options.right).await?;
 
-    // Save NewRL to binary file
-    write_new_rl_to_file(output_file, &new_rl).await?;
-    println!("\n✅ NewRL successfully saved to '{}'", output_file);
+    say!("🔍 Comparing '{}' with '{}'\n", options.left, options.right);
+    let mut differences = 0;
+    for field in compare_fields(&left, &right, &options.fields) {
+        if field.is_equal() {
+            if options.verbose {
+                say!("  = {}: {}", field.field, field.left);
+            }
+        } else {
+            differences += 1;
+            say!("  ≠ {}:", field.field);
+            say!("      a: {}", field.left);
+            say!("      b: {}", field.right);
+        }
+    }
 
-    // Read back the saved NewRL for verification
-    println!("📂 Re-opening saved NewRL file for verification...");
-    let loaded_new = read_new_rl_file(output_file).await?;
-    println!("{}", loaded_new);
+    if differences > 0 {
+        say!("\n❌ {} field(s) differ", differences);
+        std::process::exit(1);
+    }
 
-    // Compare structures
-    println!("🔍 Comparing saved NewRL structure with original...");
-    if new_rl == *loaded_new {
-        println!("✓ Verification successful: structures match!");
+    if options.fields.is_empty() {
+        say!("✅ Files are identical");
     } else {
-        println!("❌ Verification failed: structures do not match.");
-        println!("--- ORIGINAL NewRL ---\n{:?}", new_rl);
-        println!("--- LOADED  NewRL ---\n{:?}", loaded_new);
+        say!("✅ Selected fields are identical");
+    }
+
+    Ok(())
+}
+
+/// Writes the first NewRL file with the winners of both files combined
+async fn run_merge(options: &MergeOptions) -> Result<(), ConvertError> {
+    let first = read_new_rl_file(&options.first).await?;
+    let second = read_new_rl_file(&options.second).await?;
+
+    say!(
+        "🔀 Merging winners of '{}' and '{}'\n",
+        options.first,
+        options.second
+    );
+    for field in compare_scalars(&first, &second) {
+        if !field.is_equal() && field.field != "winners_counter" {
+            warn!(
+                "⚠️  Warning: {} differs, keeping '{}' from the first file (second has '{}')",
+                field.field, field.left, field.right
+            );
+        }
+    }
+
+    let merged = first.merge_winners(&second);
+    let recorded = first.winners_chronological().count() + second.winners_chronological().count();
+    let distinct = merged.winners_counter as usize;
+    let kept = merged.winners_chronological().count();
+    say!(
+        "  Winners: {} + {} → {} ({} duplicate(s) dropped)",
+        first.winners_chronological().count(),
+        second.winners_chronological().count(),
+        kept,
+        recorded - distinct
+    );
+    if kept < distinct {
+        warn!(
+            "⚠️  Warning: {} distinct winners exceed the history of {}, the {} oldest are dropped",
+            distinct,
+            RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY,
+            distinct - kept
+        );
+    }
+
+    let (digest, size) = write_new_rl_to_file(
+        Path::new(&options.output),
+        &merged,
+        OutputFormat::Binary,
+        false,
+        false,
+    )
+    .await?;
+    say!(
+        "✅ Merged state written to '{}' ({} bytes)",
+        options.output,
+        size
+    );
+    say!("🔐 Output K12: {}", to_hex(&digest));
+
+    Ok(())
+}
+
+/// Writes a NewRL file as it would look after the next epoch boundary
+async fn run_simulate_rollover(options: &RolloverOptions) -> Result<(), ConvertError> {
+    let mut new_rl = read_new_rl_file(&options.file).await?;
+    let (players, price, schedule) = (
+        new_rl.active_player_count(),
+        new_rl.ticket_price,
+        new_rl.schedule_days(),
+    );
+    new_rl.simulate_rollover(options.winner)?;
+
+    say!("⏭️  Simulating the epoch rollover of '{}'\n", options.file);
+    say!("  Players cleared: {}", players);
+    say!(
+        "  Ticket price:    {} → {} units",
+        price,
+        new_rl.ticket_price
+    );
+    say!(
+        "  Schedule:        {} → {}",
+        schedule,
+        new_rl.schedule_days()
+    );
+    if let Some(winner) = &options.winner {
+        say!(
+            "  Winner recorded: {} (winners_counter now {})",
+            winner,
+            new_rl.winners_counter
+        );
+    }
+
+    let (digest, size) = write_new_rl_to_file(
+        Path::new(&options.output),
+        &new_rl,
+        OutputFormat::Binary,
+        false,
+        false,
+    )
+    .await?;
+    say!(
+        "\n✅ Rolled-over state written to '{}' ({} bytes)",
+        options.output,
+        size
+    );
+    say!("🔐 Output K12: {}", to_hex(&digest));
+
+    Ok(())
+}
+
+/// Offset of the first byte at which `a` and `b` differ, comparing up to
+/// the shorter length
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter().zip(b.iter()).position(|(x, y)| x != y)
+}
+
+/// Decodes an OldRL file, re-encodes it and compares the result byte for byte
+async fn run_verify(path: &str) -> Result<(), ConvertError> {
+    let file = tokio::fs::read(path).await?;
+    let original = gunzip_if_compressed(&file)?;
+    let old_rl = read_old_rl_from_bytes(&original)?;
+    let encoded = write_old_rl_to_bytes(&old_rl);
+
+    say!(
+        "🔍 Round-trip check of '{}' ({} bytes)\n",
+        path,
+        original.len()
+    );
+
+    if let Some(offset) = first_difference(&original, &encoded) {
+        let differing = original
+            .iter()
+            .zip(encoded.iter())
+            .filter(|(a, b)| a != b)
+            .count();
+        let start = offset & !0xf;
+        let end = (start + 32).min(original.len());
+        say!(
+            "❌ First difference at offset {} (0x{:x}): file has 0x{:02x}, re-encoded has 0x{:02x}",
+            offset,
+            offset,
+            original[offset],
+            encoded[offset]
+        );
+        say!("   {} byte(s) differ in total", differing);
+        say!("   File:\n{}", hexdump_at(&original[start..end], start));
+        say!(
+            "   Re-encoded:\n{}",
+            hexdump_at(&encoded[start..end], start)
+        );
         std::process::exit(1);
     }
 
-    println!("\n✅ Conversion completed successfully!");
+    say!("✅ Re-encoded state is byte-identical to the input");
+
+    Ok(())
+}
+
+/// Prints the active players of a state file, one per line and nothing else
+async fn run_players(options: &PlayersOptions) -> Result<(), ConvertError> {
+    use std::io::Write;
+
+    let players: Vec<Id> = match read_contract_file(&options.file, false, InputEncoding::Binary)
+        .await?
+        .0
+    {
+        InputState::Old(old_rl, _) => old_rl.players_iter().copied().collect(),
+        InputState::New(new_rl) => new_rl.players_iter().copied().collect(),
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    for player in &players {
+        if options.base64 {
+            writeln!(stdout, "{}", player.to_base64())?;
+        } else {
+            writeln!(stdout, "{}", player)?;
+        }
+    }
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// Prints the winners history of a state file as CSV, oldest winner first,
+/// optionally limited to an epoch range
+async fn run_winners_csv(options: &WinnersCsvOptions) -> Result<(), ConvertError> {
+    use std::io::Write;
+
+    let (start, end) = options.epochs.unwrap_or((u16::MIN, u16::MAX));
+
+    // Old winners have no day of week, so that column stays blank for them
+    let rows: Vec<(Id, u64, u32, u16, Option<u8>)> =
+        match read_contract_file(&options.file, false, InputEncoding::Binary)
+            .await?
+            .0
+        {
+            InputState::Old(old_rl, _) => old_rl
+                .winners_in_epochs(start, end)
+                .map(|w| (w.winner_address, w.revenue, w.tick, w.epoch, None))
+                .collect(),
+            InputState::New(new_rl) => new_rl
+                .winners_in_epochs(start, end)
+                .map(|w| {
+                    (
+                        w.winner_address,
+                        w.revenue,
+                        w.tick,
+                        w.epoch,
+                        Some(w.day_of_week),
+                    )
+                })
+                .collect(),
+        };
+
+    let mut stdout = std::io::stdout().lock();
+    writeln!(
+        stdout,
+        "index,winner_identity,revenue,tick,epoch,day_of_week"
+    )?;
+    for (i, (address, revenue, tick, epoch, day_of_week)) in rows.iter().enumerate() {
+        let day_of_week = day_of_week.map(|d| d.to_string()).unwrap_or_default();
+        writeln!(
+            stdout,
+            "{},{},{},{},{},{}",
+            i + 1,
+            address,
+            revenue,
+            tick,
+            epoch,
+            day_of_week
+        )?;
+    }
+    stdout.flush()?;
+
+    Ok(())
+}
+
+/// Whether two paths name the same file; either may not exist yet
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    resolve_path(a) == resolve_path(b)
+}
+
+/// Absolute form of `path` with symlinks, `.` and `..` resolved as far as
+/// the path exists; the missing tail is appended as written
+fn resolve_path(path: &Path) -> PathBuf {
+    if let Ok(resolved) = path.canonicalize() {
+        return resolved;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            resolve_path(parent).join(name)
+        }
+        (_, Some(name)) => resolve_path(Path::new(".")).join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Layout name, active players, prizes oldest first, their sum and the
+/// current state of a decoded file, as printed by `stats`
+fn state_stats(input_state: &InputState) -> (&'static str, usize, Vec<u64>, u128, EState) {
+    match input_state {
+        InputState::Old(old_rl, _) => (
+            "OldRL",
+            old_rl.active_player_count(),
+            old_rl.winners_chronological().map(|w| w.revenue).collect(),
+            old_rl.total_revenue(),
+            old_rl.current_state,
+        ),
+        InputState::New(new_rl) => (
+            "NewRL",
+            new_rl.active_player_count(),
+            new_rl.winners_chronological().map(|w| w.revenue).collect(),
+            new_rl.total_revenue(),
+            new_rl.current_state,
+        ),
+    }
+}
+
+/// Prints player, winner and prize totals of a state file
+async fn run_stats(path: &str) -> Result<(), ConvertError> {
+    let (input_state, _) = read_contract_file(path, false, InputEncoding::Binary).await?;
+    let (layout, active_players, revenues, total, state) = state_stats(&input_state);
+
+    say!("📊 Stats for '{}' ({})\n", path, layout);
+    say!("  Active players:  {}", active_players);
+    say!("  Winners:         {}", revenues.len());
+    say!("  Total revenue:   {} units", total);
+    match (revenues.iter().min(), revenues.iter().max()) {
+        (Some(min), Some(max)) => {
+            say!(
+                "  Average prize:   {} units",
+                total / revenues.len() as u128
+            );
+            say!("  Min / max prize: {} / {} units", min, max);
+        }
+        _ => {
+            say!("  Average prize:   -");
+            say!("  Min / max prize: -");
+        }
+    }
+    say!("  Current state:   {}", state.as_str());
+
+    Ok(())
+}
+
+/// Runs every consistency check on a state file and prints the outcome of
+/// each, grouped by category; exits 1 when any check fails
+async fn run_validate(path: &str) -> Result<(), ConvertError> {
+    let (layout, checks) = match read_contract_file(path, false, InputEncoding::Binary)
+        .await?
+        .0
+    {
+        InputState::Old(old_rl, _) => ("OldRL", checks::check_old(&old_rl)),
+        InputState::New(new_rl) => ("NewRL", checks::check_new(&new_rl)),
+    };
+
+    say!("🩺 Validating '{}' ({})", path, layout);
+    let mut category = None;
+    for check in &checks {
+        if category != Some(check.category) {
+            category = Some(check.category);
+            say!("\n  {}", check.category.as_str());
+        }
+        match &check.failure {
+            None => say!("    ✅ {}", check.name),
+            Some(reason) => say!("    ❌ {}: {}", check.name, reason),
+        }
+    }
+
+    let failed = checks.iter().filter(|check| !check.passed()).count();
+    if failed > 0 {
+        say!("\n❌ {} of {} checks failed", failed, checks.len());
+        std::process::exit(1);
+    }
+    say!("\n✅ All {} checks passed", checks.len());
+
+    Ok(())
+}
+
+/// Prints the fee shares implied by the recorded prizes of a state file
+async fn run_analyze(path: &str) -> Result<(), ConvertError> {
+    let (layout, split, revenues) = match read_contract_file(path, false, InputEncoding::Binary)
+        .await?
+        .0
+    {
+        InputState::Old(old_rl, _) => (
+            "OldRL",
+            FeeSplit::from(old_rl.as_ref()),
+            old_rl
+                .winners_chronological()
+                .map(|w| w.revenue)
+                .collect::<Vec<u64>>(),
+        ),
+        InputState::New(new_rl) => (
+            "NewRL",
+            FeeSplit::from(new_rl.as_ref()),
+            new_rl
+                .winners_chronological()
+                .map(|w| w.revenue)
+                .collect::<Vec<u64>>(),
+        ),
+    };
+
+    say!("🧮 Fee analysis of '{}' ({})\n", path, layout);
+    say!(
+        "  Fees:            team {}%, distribution {}%, winner {}%, burn {}%",
+        split.team,
+        split.distribution,
+        split.winner,
+        split.burn
+    );
+    let Some(totals) = split.implied_totals(revenues.iter().copied()) else {
+        warn!("⚠️  Warning: winner_fee_percent is 0, the gross revenue cannot be derived from the prizes");
+        say!("  Winners:         {}", revenues.len());
+        say!(
+            "  Paid to winners: {} units",
+            revenues.iter().map(|&r| u128::from(r)).sum::<u128>()
+        );
+        return Ok(());
+    };
+    say!("  Winners:         {}", totals.winners);
+    say!("  Paid to winners: {} units", totals.paid_to_winners);
+    say!("  Implied gross:   {} units", totals.gross);
+    say!("  Team:            {} units", totals.team);
+    say!("  Distribution:    {} units", totals.distribution);
+    say!("  Burn:            {} units", totals.burn);
+
+    Ok(())
+}
+
+/// `(revenue, tick, epoch)` of every win of `address`, oldest first
+fn winnings_of(input_state: &InputState, address: &Id) -> Vec<(u64, u32, u16)> {
+    match input_state {
+        InputState::Old(old_rl, _) => old_rl
+            .winners_chronological()
+            .filter(|w| w.winner_address == *address)
+            .map(|w| (w.revenue, w.tick, w.epoch))
+            .collect(),
+        InputState::New(new_rl) => new_rl
+            .winners_chronological()
+            .filter(|w| w.winner_address == *address)
+            .map(|w| (w.revenue, w.tick, w.epoch))
+            .collect(),
+    }
+}
+
+/// Prints every recorded win of one address, oldest first
+async fn run_winners_for(options: &WinnersForOptions) -> Result<(), ConvertError> {
+    let (input_state, _) = read_contract_file(&options.file, false, InputEncoding::Binary).await?;
+    let wins = winnings_of(&input_state, &options.address);
+
+    say!("🏆 Winnings of {} in '{}'\n", options.address, options.file);
+    if wins.is_empty() {
+        say!("  No winnings found");
+        return Ok(());
+    }
+    for (i, (revenue, tick, epoch)) in wins.iter().enumerate() {
+        say!(
+            "  {:>4}. Revenue: {} units, Tick: {}, Epoch: {}",
+            i + 1,
+            revenue,
+            tick,
+            epoch
+        );
+    }
+    let total: u128 = wins
+        .iter()
+        .map(|(revenue, _, _)| u128::from(*revenue))
+        .sum();
+    say!("\n  Total: {} units in {} win(s)", total, wins.len());
+
+    Ok(())
+}
+
+/// Runs the conversion described by `options`
+async fn run(options: &Options) -> Result<(), ConvertError> {
+    let input = Path::new(&options.input);
+    let output_file = match &options.output_file {
+        Some(output_file) => output_file,
+        None => return run_batch(input, options).await,
+    };
+
+    // Check input file existence
+    if !is_stdio(input) && !input.exists() {
+        eprintln!("❌ Error: input file '{}' not found", options.input);
+        std::process::exit(2);
+    }
+
+    // Writing over the input would destroy the OldRL it was converted from
+    let output = Path::new(output_file);
+    if !options.force && !is_stdio(input) && !is_stdio(output) && is_same_file(input, output) {
+        eprintln!(
+            "❌ Error: input and output both resolve to '{}'; refusing to overwrite the source (use --force to allow)",
+            options.input
+        );
+        std::process::exit(1);
+    }
+
+    convert_file(input, output, options).await?;
+
+    say!("\n✅ Conversion completed successfully!");
+
+    Ok(())
+}
+
+/// Lists the next draw times of a NewRL file (`schedule-preview`)
+async fn run_schedule_preview(options: &SchedulePreviewOptions) -> Result<(), ConvertError> {
+    let new_rl = read_new_rl_file(&options.file).await?;
+    new_rl.validate_schedule()?;
+
+    let from = options.from.unwrap_or_else(chrono::Utc::now);
+    say!(
+        "🗓️  Upcoming draws of '{}' (schedule: {}, at {:02}:00 UTC)\n",
+        options.file,
+        new_rl.schedule_days(),
+        new_rl.draw_hour
+    );
+    let draws = upcoming_draws(
+        new_rl.schedule,
+        new_rl.draw_hour,
+        from,
+        options.second_wednesday,
+        options.count,
+    );
+    if draws.is_empty() {
+        say!("  No draw days scheduled");
+        return Ok(());
+    }
+    for (i, draw) in draws.iter().enumerate() {
+        let note = if draw.needs_second_wednesday {
+            ", only if it is the second Wednesday"
+        } else {
+            ""
+        };
+        say!(
+            "  {:>3}. {} ({}{})",
+            i + 1,
+            draw.at.format("%Y-%m-%d %H:%M UTC"),
+            draw.weekday,
+            note
+        );
+    }
+    if draws.iter().any(|draw| draw.needs_second_wednesday) {
+        say!("\n  Pass --second-wednesday <date> to apply the Two-Wednesdays rule");
+    }
+
+    Ok(())
+}
+
+/// Writes a random NewRL state (`gen`)
+#[cfg(feature = "gen")]
+async fn run_gen(options: &cli::GenOptions) -> Result<(), ConvertError> {
+    let new_rl = gen::generate(options);
+    let (digest, size) = write_new_rl_to_file(
+        Path::new(&options.output),
+        &new_rl,
+        OutputFormat::Binary,
+        false,
+        false,
+    )
+    .await?;
+    say!(
+        "🎲 Generated {} player(s) and {} winner(s) with seed {} into '{}' ({} bytes)",
+        options.players,
+        options.winners,
+        options.seed,
+        options.output,
+        size
+    );
+    say!("🔐 Output K12: {}", to_hex(&digest));
 
     Ok(())
 }
+
+#[tokio::main]
+async fn main() {
+    // Parse command line arguments
+    let args: Vec<String> = std::env::args().collect();
+
+    let command = match cli::parse_args(&args[1..]) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("❌ Error: {}\n", message);
+            cli::print_usage(&args[0]);
+            std::process::exit(1);
+        }
+    };
+
+    if let Command::Help = command {
+        cli::print_usage(&args[0]);
+        return;
+    }
+
+    // Keep the piped binary stream clean when writing NewRL to stdout
+    if let Command::Convert(options) = &command {
+        if options.output_file.as_deref() == Some("-") {
+            STDOUT_IS_DATA.store(true, Ordering::Relaxed);
+        }
+        QUIET.store(options.quiet, Ordering::Relaxed);
+        PREVIEW_BYTES.store(options.preview_bytes, Ordering::Relaxed);
+    }
+
+    // Diagnostics go through `log`; RUST_LOG=info or RUST_LOG=debug shows progress
+    let default_filter = if QUIET.load(Ordering::Relaxed) {
+        "error"
+    } else {
+        "warn"
+    };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default_filter))
+        .init();
+
+    // `players` and `winners-csv` output is meant for pipes, so it gets no banner
+    if !matches!(command, Command::Players(_) | Command::WinnersCsv(_)) {
+        say!("\n🎰 Random Lottery Contract - State Converter\n");
+    }
+
+    let result = match &command {
+        Command::Convert(options) => run(options).await,
+        Command::Diff(options) => run_diff(options).await,
+        Command::Verify(path) => run_verify(path).await,
+        Command::Players(options) => run_players(options).await,
+        Command::WinnersCsv(options) => run_winners_csv(options).await,
+        Command::Stats(path) => run_stats(path).await,
+        Command::Validate(path) => run_validate(path).await,
+        Command::Analyze(path) => run_analyze(path).await,
+        Command::WinnersFor(options) => run_winners_for(options).await,
+        Command::Merge(options) => run_merge(options).await,
+        Command::Layout(formats) => {
+            print_field_tables(formats);
+            Ok(())
+        }
+        Command::SchedulePreview(options) => run_schedule_preview(options).await,
+        Command::SimulateRollover(options) => run_simulate_rollover(options).await,
+        Command::Help => Ok(()),
+        #[cfg(feature = "gen")]
+        Command::Gen(options) => run_gen(options).await,
+    };
+    if let Err(e) = result {
+        eprintln!("❌ Error: {}", e);
+        std::process::exit(e.exit_code());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rlconverter::common::RL_MAX_NUMBER_OF_PLAYERS;
+    use rlconverter::old_rl::PlayerHashSet;
+
+    #[test]
+    fn force_overwrites_without_asking() {
+        for (is_tty, ci) in [(false, false), (false, true), (true, false), (true, true)] {
+            assert_eq!(
+                overwrite_decision(is_tty, ci, true),
+                OverwriteDecision::Overwrite
+            );
+        }
+    }
+
+    #[test]
+    fn terminal_prompts() {
+        assert_eq!(
+            overwrite_decision(true, false, false),
+            OverwriteDecision::Prompt
+        );
+        assert_eq!(
+            overwrite_decision(true, true, false),
+            OverwriteDecision::Prompt
+        );
+    }
+
+    #[test]
+    fn non_terminal_never_prompts() {
+        // Prompting would block on a read from stdin that nobody answers
+        assert_eq!(
+            overwrite_decision(false, false, false),
+            OverwriteDecision::Overwrite
+        );
+        assert_eq!(
+            overwrite_decision(false, true, false),
+            OverwriteDecision::Refuse
+        );
+    }
+
+    #[test]
+    fn canonical_old_image_re_encodes_identically() {
+        let mut old = OldRL::default();
+        old.team_address.data = [9; 32];
+        old.ticket_price = 1_000;
+        old.winners_info_next_empty_index = 3;
+        let image = write_old_rl_to_bytes(&old);
+        let encoded = write_old_rl_to_bytes(&read_old_rl_from_bytes(&image).unwrap());
+        assert_eq!(first_difference(&image, &encoded), None);
+    }
+
+    #[test]
+    fn dirty_padding_is_reported_at_its_offset() {
+        let mut image = write_old_rl_to_bytes(&OldRL::default());
+        // Padding between burn_percent and ticket_price
+        let padding = std::mem::offset_of!(OldRL, burn_percent) + 1;
+        image[padding] = 0xEE;
+        let encoded = write_old_rl_to_bytes(&read_old_rl_from_bytes(&image).unwrap());
+        assert_eq!(first_difference(&image, &encoded), Some(padding));
+    }
+
+    /// Fresh directory under the system temp dir, unique per test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rlconverter-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn same_file_guard_triggers_on_identical_paths() {
+        let dir = temp_dir("same-file");
+        std::fs::create_dir(dir.join("sub")).unwrap();
+        let input = dir.join("contract.185");
+        std::fs::write(&input, b"state").unwrap();
+
+        assert!(is_same_file(&input, &input));
+        assert!(is_same_file(&input, &dir.join("sub/../contract.185")));
+        assert!(is_same_file(&input, &dir.join("./contract.185")));
+        assert!(!is_same_file(&input, &dir.join("contract_new.185")));
+        assert!(!is_same_file(&input, &dir.join("sub/contract.185")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn stats_sum_and_count_the_prizes() {
+        let mut players = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+        players[0].data = [1; 32];
+        players[5].data = [2; 32];
+        let mut old = OldRL {
+            players: PlayerHashSet::from_players(&players),
+            winners_info_next_empty_index: 3,
+            current_state: EState::Selling,
+            ..OldRL::default()
+        };
+        for (i, revenue) in [300, 100, 200].into_iter().enumerate() {
+            old.winners[i] = WinnerInfo {
+                winner_address: Id { data: [9; 32] },
+                revenue,
+                epoch: 100 + i as u16,
+                tick: 0,
+            };
+        }
+
+        let (layout, active_players, revenues, total, state) =
+            state_stats(&InputState::Old(Box::new(old), None));
+        assert_eq!(layout, "OldRL");
+        assert_eq!(active_players, 2);
+        assert_eq!(revenues, [300, 100, 200]);
+        assert_eq!(total, 600);
+        assert_eq!(state, EState::Selling);
+
+        let new_rl = convert(&old);
+        let (layout, _, revenues, total, _) = state_stats(&InputState::New(Box::new(new_rl)));
+        assert_eq!(layout, "NewRL");
+        assert_eq!(revenues.len(), 3);
+        assert_eq!(total, 600);
+    }
+
+    #[test]
+    fn wrapped_history_is_flagged_and_warned_about() {
+        let mut old = OldRL {
+            winners_info_next_empty_index: RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY as u64 + 6,
+            ..OldRL::default()
+        };
+        for (i, winner) in old.winners.iter_mut().enumerate() {
+            winner.winner_address = Id { data: [1; 32] };
+            winner.revenue = 10;
+            winner.epoch = i as u16;
+        }
+        let new_rl = convert(&old);
+        assert!(new_rl.has_wrapped());
+        let warning = wrap_warning(&new_rl).unwrap();
+        assert!(warning.contains("1030 winners recorded"));
+        assert!(warning.contains("only the last 1024"));
+
+        old.winners_info_next_empty_index = 2;
+        for winner in &mut old.winners[2..] {
+            *winner = WinnerInfo::default();
+        }
+        let new_rl = convert(&old);
+        assert!(!new_rl.has_wrapped());
+        assert_eq!(wrap_warning(&new_rl), None);
+    }
+
+    #[test]
+    fn winnings_of_lists_only_the_given_address() {
+        let alice = Id { data: [1; 32] };
+        let bob = Id { data: [2; 32] };
+        let mut old = OldRL {
+            winners_info_next_empty_index: 4,
+            ..OldRL::default()
+        };
+        for (i, (address, revenue)) in [(alice, 100), (bob, 200), (alice, 300), (bob, 400)]
+            .into_iter()
+            .enumerate()
+        {
+            old.winners[i] = WinnerInfo {
+                winner_address: address,
+                revenue,
+                epoch: 150 + i as u16,
+                tick: 1_000 * i as u32,
+            };
+        }
+
+        let state = InputState::Old(Box::new(old), None);
+        assert_eq!(
+            winnings_of(&state, &alice),
+            [(100, 0, 150), (300, 2_000, 152)]
+        );
+        assert_eq!(
+            winnings_of(&InputState::New(Box::new(convert(&old))), &bob),
+            [(200, 1_000, 151), (400, 3_000, 153)]
+        );
+        assert!(winnings_of(&state, &Id { data: [3; 32] }).is_empty());
+    }
+
+    #[test]
+    fn inconsistent_winner_slot_is_warned_about() {
+        let mut old = OldRL {
+            winners_info_next_empty_index: 3,
+            ..OldRL::default()
+        };
+        for (i, revenue) in [100, 0, 300].into_iter().enumerate() {
+            old.winners[i] = WinnerInfo {
+                winner_address: Id { data: [1; 32] },
+                revenue,
+                epoch: 150 + i as u16,
+                tick: 0,
+            };
+        }
+        let new_rl = convert(&old);
+        assert_eq!(new_rl.inconsistent_winner_slots(), [1]);
+        let warning = inconsistent_winners_warning(&new_rl).unwrap();
+        assert!(warning.contains("slot(s) [1]"));
+
+        old.winners[1].revenue = 200;
+        assert_eq!(inconsistent_winners_warning(&convert(&old)), None);
+    }
+
+    #[test]
+    fn converting_a_state_with_players_does_not_warn_about_the_counter() {
+        let mut players = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+        players[0].data = [3; 32];
+        let old = OldRL {
+            players: PlayerHashSet::from_players(&players),
+            ..OldRL::default()
+        };
+        let mut new_rl = convert(&old);
+        assert_eq!(player_counter_warning(&new_rl), None);
+
+        new_rl.player_counter = 0;
+        let warning = player_counter_warning(&new_rl).unwrap();
+        assert!(warning.contains("player_counter is 0 but 1 player slot(s) are populated"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn json_compact_is_one_short_json_line() {
+        let mut players = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+        players[3].data = [3; 32];
+        let new_rl = convert(&OldRL {
+            players: PlayerHashSet::from_players(&players),
+            ..OldRL::default()
+        });
+
+        let mut line = Vec::new();
+        let (_, size) =
+            write_new_rl_encoded(&mut line, &new_rl, OutputFormat::JsonCompact, false, false)
+                .await
+                .unwrap();
+        assert_eq!(size, line.len());
+        let text = String::from_utf8(line).unwrap();
+        assert_eq!(text.find('\n'), Some(text.len() - 1));
+        // Empty player and winner slots are left out
+        assert!(text.len() < 2_000, "{} bytes", text.len());
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert!(value.is_object());
+    }
+
+    #[test]
+    fn limited_dump_ends_lists_with_a_footer() {
+        let mut players = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+        for (i, player) in players.iter_mut().take(5).enumerate() {
+            player.data = [i as u8 + 1; 32];
+        }
+        let mut old = OldRL {
+            players: PlayerHashSet::from_players(&players),
+            winners_info_next_empty_index: 3,
+            ..OldRL::default()
+        };
+        for (i, winner) in old.winners.iter_mut().take(3).enumerate() {
+            winner.winner_address.data = [9; 32];
+            winner.revenue = 100;
+            winner.epoch = 150 + i as u16;
+        }
+        let new_rl = convert(&old);
+
+        let limited = DisplayConfig {
+            limit: Some(2),
+            ..DisplayConfig::default()
+        };
+        let dump = Dump(&new_rl, limited).to_string();
+        assert!(dump.contains("    ... and 3 more\n"));
+        assert!(dump.contains("    ... and 1 more\n"));
+        assert!(!dump.contains("    3. "));
+        let dump = Dump(&old, limited).to_string();
+        assert!(dump.contains("    ... and 3 more\n"));
+
+        assert!(!Dump(&new_rl, DisplayConfig::default())
+            .to_string()
+            .contains("more\n"));
+    }
+
+    #[tokio::test]
+    async fn interrupted_write_leaves_the_destination_untouched() {
+        let dir = temp_dir("atomic");
+        let output = dir.join("contract0016.185");
+        std::fs::write(&output, b"previous state").unwrap();
+        let new_rl = convert(&OldRL::default());
+
+        // A directory in the way of the temporary file stops the write before the rename
+        std::fs::create_dir(temp_path(&output)).unwrap();
+        let result =
+            write_new_rl_to_file(&output, &new_rl, OutputFormat::Binary, false, false).await;
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&output).unwrap(), b"previous state");
+
+        std::fs::remove_dir(temp_path(&output)).unwrap();
+        let (_, size) = write_new_rl_to_file(&output, &new_rl, OutputFormat::Binary, false, false)
+            .await
+            .unwrap();
+        assert_eq!(size, NEW_RL_SIZE);
+        assert_eq!(
+            std::fs::read(&output).unwrap(),
+            write_new_rl_to_bytes(&new_rl)
+        );
+        assert!(!temp_path(&output).exists());
+    }
+
+    #[tokio::test]
+    async fn endless_input_stops_one_byte_past_the_limit() {
+        let result = read_bounded(tokio::io::repeat(0), MAX_INPUT_LEN).await;
+        assert!(matches!(
+            result,
+            Err(ConvertError::InputTooLarge(limit)) if limit == MAX_INPUT_LEN
+        ));
+
+        let exact = read_bounded(tokio::io::repeat(0).take(100), 100).await;
+        assert_eq!(exact.unwrap().len(), 100);
+    }
+
+    /// Hands out `data` a few bytes per read, failing every other read with
+    /// `Interrupted` like a slow pipe hit by signals
+    struct TrickleReader {
+        data: Vec<u8>,
+        position: usize,
+        interrupt: bool,
+    }
+
+    impl AsyncRead for TrickleReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Poll::Ready(Err(std::io::ErrorKind::Interrupted.into()));
+            }
+            let end = (self.position + 7.min(buf.remaining())).min(self.data.len());
+            buf.put_slice(&self.data[self.position..end]);
+            self.position = end;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn small_interrupted_reads_are_joined() {
+        let image = rlconverter::write_old_rl_to_bytes(&OldRL::default());
+        let reader = TrickleReader {
+            data: image.clone(),
+            position: 0,
+            interrupt: false,
+        };
+        assert_eq!(read_bounded(reader, MAX_INPUT_LEN).await.unwrap(), image);
+
+        // An input that ends early comes back short for the size check
+        let reader = TrickleReader {
+            data: image[..100].to_vec(),
+            position: 0,
+            interrupt: false,
+        };
+        assert_eq!(
+            read_bounded(reader, MAX_INPUT_LEN).await.unwrap().len(),
+            100
+        );
+    }
+
+    #[test]
+    fn color_never_writes_no_escape_codes() {
+        let args: Vec<String> = ["--color", "never", "in.185", "out.185"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let Ok(Command::Convert(options)) = cli::parse_args(&args) else {
+            panic!("--color never should parse as a conversion");
+        };
+        let config = options.display_config();
+        assert!(!config.color);
+
+        // Selling at price 0 with a broken fee split is highlighted when coloring
+        let new_rl = convert(&OldRL {
+            team_fee_percent: 10,
+            current_state: EState::Selling,
+            ..OldRL::default()
+        });
+        let plain = Dump(&new_rl, config).to_string();
+        assert!(!plain.contains('\x1b'));
+        assert_eq!(plain, new_rl.to_string());
+
+        let colored = DisplayConfig {
+            color: true,
+            ..config
+        };
+        assert!(Dump(&new_rl, colored).to_string().contains('\x1b'));
+    }
+
+    #[test]
+    fn unknown_diff_field_lists_the_valid_names() {
+        let args: Vec<String> = ["diff", "--field", "owner", "a.185", "b.185"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let Err(message) = cli::parse_args(&args) else {
+            panic!("an unknown field should be rejected");
+        };
+        assert!(message.starts_with("unknown field 'owner'"));
+        assert!(message.contains("owner_address"));
+    }
+}
@@ -1,18 +1,33 @@
-use crate::common::{EState, Id, RL_MAX_NUMBER_OF_PLAYERS, RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY};
-use crate::old_rl::OldRL;
-use std::fmt::{self, Display, Formatter};
-use std::mem::MaybeUninit;
+use crate::common::{
+    centered, DisplayConfig, EState, FieldReader, FieldWriter, Id, K12Hasher,
+    RL_MAX_NUMBER_OF_PLAYERS, RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY,
+};
+use crate::error::ConvertError;
+use crate::old_rl::{DrawGuard, OldRL, WinnerInfo};
+use crate::schedule::Schedule;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use core::hash::{Hash, Hasher};
+use core::mem::{offset_of, size_of, MaybeUninit};
 
+/// One entry of the winners history.
+///
+/// New fields may be added with future layouts, so outside this crate the
+/// struct is built with `WinnerInfoNew::new` rather than a literal.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(C)]
+#[non_exhaustive]
 pub struct WinnerInfoNew {
     pub winner_address: Id,
     pub revenue: u64,
     pub tick: u32,
     pub epoch: u16,
+    /// UTC weekday of the draw, 0 = Wednesday ... 6 = Tuesday (same as the schedule bits).
     pub day_of_week: u8,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for WinnerInfoNew {
     fn default() -> Self {
         Self {
@@ -25,13 +40,117 @@ impl Default for WinnerInfoNew {
     }
 }
 
+impl From<&WinnerInfo> for WinnerInfoNew {
+    /// Copies the old entry; `day_of_week` is unknown and left at 0.
+    fn from(w: &WinnerInfo) -> Self {
+        WinnerInfoNew {
+            winner_address: w.winner_address,
+            revenue: w.revenue,
+            tick: w.tick,
+            epoch: w.epoch,
+            day_of_week: 0,
+        }
+    }
+}
+
+impl From<WinnerInfo> for WinnerInfoNew {
+    fn from(w: WinnerInfo) -> Self {
+        WinnerInfoNew::from(&w)
+    }
+}
+
+impl WinnerInfoNew {
+    /// Builds a history entry from every field of the current layout.
+    ///
+    /// ```
+    /// use rlconverter::common::Id;
+    /// use rlconverter::new_rl::WinnerInfoNew;
+    ///
+    /// let winner = WinnerInfoNew::new(Id { data: [7; 32] }, 500, 12_345, 100, 2);
+    /// assert_eq!(winner.revenue, 500);
+    /// assert_eq!(winner.day_of_week, 2);
+    /// ```
+    pub fn new(winner_address: Id, revenue: u64, tick: u32, epoch: u16, day_of_week: u8) -> Self {
+        Self {
+            winner_address,
+            revenue,
+            tick,
+            epoch,
+            day_of_week,
+        }
+    }
+
+    /// Writes the one-line `Display` form with the address rendered as
+    /// described by `config`.
+    pub fn fmt_with_config(&self, f: &mut Formatter<'_>, config: &DisplayConfig) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} units, epoch {}, tick {}, day of week {}",
+            config.id("winner", &self.winner_address),
+            self.revenue,
+            self.epoch,
+            self.tick,
+            self.day_of_week
+        )
+    }
+}
+
+impl Display for WinnerInfoNew {
+    /// e.g. `BZBQ...RCFH: 500 units, epoch 100, tick 12345, day of week 2`
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.fmt_with_config(f, &DisplayConfig::default())
+    }
+}
+
+/// Price and schedule that take effect at the next epoch boundary.
+///
+/// Built with `NextEpochData::new` outside this crate, see `WinnerInfoNew`.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
 pub struct NextEpochData {
     pub new_price: u64,
     pub schedule: u8,
 }
 
+impl Display for NextEpochData {
+    /// e.g. `price 1000000 units, schedule 0b00000101 (WED, FRI)`
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "price {} units, schedule 0b{:08b} ({})",
+            self.new_price,
+            self.schedule,
+            self.schedule_days()
+        )
+    }
+}
+
+impl NextEpochData {
+    pub fn new(new_price: u64, schedule: u8) -> Self {
+        Self {
+            new_price,
+            schedule,
+        }
+    }
+
+    /// `schedule` as a typed weekday set.
+    pub fn schedule_days(&self) -> Schedule {
+        Schedule::from_bits(self.schedule)
+    }
+
+    /// Checks `schedule` with the same bounds as `NewRL::validate_schedule`,
+    /// so `NewRL::apply_next_epoch` never moves an invalid bitmask into the
+    /// live field.
+    pub fn validate(&self) -> Result<(), ScheduleError> {
+        if !self.schedule_days().is_valid() {
+            return Err(ScheduleError::InvalidNextScheduleBits(self.schedule));
+        }
+        Ok(())
+    }
+}
+
 impl Default for NextEpochData {
     fn default() -> Self {
         // Zero the entire structure, including padding bytes.
@@ -40,8 +159,13 @@ impl Default for NextEpochData {
 }
 
 /// New Random Lottery contract state structure
+///
+/// Outside this crate a state is created with `NewRL::builder()`,
+/// `NewRL::default()` or by decoding an image, never with a literal, so
+/// adding a field does not break downstream code.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct NewRL {
     /// Circular buffer storing the history of winners.
     /// Maximum capacity is defined by RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY.
@@ -87,7 +211,7 @@ pub struct NewRL {
 
     /// Schedule bitmask: bit 0 = WEDNESDAY, 1 = THURSDAY, ..., 6 = TUESDAY.
     /// If a bit is set, a draw may occur on that day (subject to drawHour and daily guard).
-    /// Wednesday also follows the "Two-Wednesdays rule".
+    /// Wednesday also follows the "Two-Wednesdays rule" (see `schedule::is_draw_allowed`).
     pub schedule: u8,
 
     /// UTC hour [0..23] when a draw is allowed to run (daily time gate).
@@ -97,25 +221,341 @@ pub struct NewRL {
     pub current_state: EState,
 }
 
+/// Size in bytes of a `NewRL` state file.
+pub const NEW_RL_SIZE: usize = size_of::<NewRL>();
+
+// Pin the on-disk layout. A size change fails the build with
+// "expected an array with a size of <expected>, found one with a size of <actual>".
+const _: [(); 48] = [(); size_of::<WinnerInfoNew>()];
+const _: [(); 16] = [(); size_of::<NextEpochData>()];
+const _: [(); 82040] = [(); NEW_RL_SIZE];
+const _: () = {
+    assert!(
+        offset_of!(NewRL, winners) == 0,
+        "NewRL.winners must be at offset 0"
+    );
+    assert!(
+        offset_of!(NewRL, players) == 49152,
+        "NewRL.players must be at offset 49152"
+    );
+    assert!(
+        offset_of!(NewRL, team_address) == 81920,
+        "NewRL.team_address must be at offset 81920"
+    );
+    assert!(
+        offset_of!(NewRL, next_epoch_data) == 81984,
+        "NewRL.next_epoch_data must be at offset 81984"
+    );
+    assert!(
+        offset_of!(NewRL, ticket_price) == 82000,
+        "NewRL.ticket_price must be at offset 82000"
+    );
+    assert!(
+        offset_of!(NewRL, winners_counter) == 82016,
+        "NewRL.winners_counter must be at offset 82016"
+    );
+    assert!(
+        offset_of!(NewRL, last_draw_date_stamp) == 82028,
+        "NewRL.last_draw_date_stamp must be at offset 82028"
+    );
+    assert!(
+        offset_of!(NewRL, team_fee_percent) == 82032,
+        "NewRL.team_fee_percent must be at offset 82032"
+    );
+    assert!(
+        offset_of!(NewRL, current_state) == 82038,
+        "NewRL.current_state must be at offset 82038"
+    );
+};
+
+/// Fee split that does not add up to 100%.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeError {
+    pub team: u8,
+    pub distribution: u8,
+    pub burn: u8,
+    pub winner: u8,
+}
+
+impl FeeError {
+    /// Sum of all four percentages, widened so it cannot overflow.
+    pub fn total(&self) -> u16 {
+        u16::from(self.team)
+            + u16::from(self.distribution)
+            + u16::from(self.burn)
+            + u16::from(self.winner)
+    }
+}
+
+impl Display for FeeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fee percentages must sum to 100, got {} (team {}% + distribution {}% + burn {}% + winner {}%)",
+            self.total(),
+            self.team,
+            self.distribution,
+            self.burn,
+            self.winner
+        )
+    }
+}
+
+impl core::error::Error for FeeError {}
+
+/// Schedule bitmask with only the seven weekday bits (0 = Wednesday ... 6 = Tuesday).
+pub const SCHEDULE_MASK: u8 = 0b0111_1111;
+
+/// Draw schedule values outside of their documented ranges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleError {
+    /// `draw_hour` is not a UTC hour in `0..=23`.
+    InvalidDrawHour(u8),
+    /// `schedule` has bits set above bit 6.
+    InvalidScheduleBits(u8),
+    /// `next_epoch_data.schedule` has bits set above bit 6.
+    InvalidNextScheduleBits(u8),
+}
+
+impl Display for ScheduleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::InvalidDrawHour(hour) => {
+                write!(f, "draw hour {} is out of range 0..=23", hour)
+            }
+            ScheduleError::InvalidScheduleBits(schedule) => write!(
+                f,
+                "schedule 0b{:08b} sets bits above bit 6 (only 7 weekdays exist)",
+                schedule
+            ),
+            ScheduleError::InvalidNextScheduleBits(schedule) => write!(
+                f,
+                "next-epoch schedule 0b{:08b} sets bits above bit 6 (only 7 weekdays exist)",
+                schedule
+            ),
+        }
+    }
+}
+
+impl core::error::Error for ScheduleError {}
+
+/// Combination of fields that no running contract should be in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    /// Tickets are on sale but cost nothing.
+    SellingWithZeroPrice,
+    /// Sales are closed, yet this many player slots are still filled.
+    LockedWithPlayers(usize),
+    /// Owner and team fee go to the same non-zero address.
+    OwnerIsTeam,
+    /// `winners_counter` cannot have produced the recorded history.
+    ImplausibleWinnersCounter { counter: u64, recorded: usize },
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::SellingWithZeroPrice => {
+                f.write_str("contract is Selling with a ticket price of 0")
+            }
+            StateError::LockedWithPlayers(count) => {
+                write!(f, "contract is Locked but still has {} player(s)", count)
+            }
+            StateError::OwnerIsTeam => {
+                f.write_str("owner_address and team_address are the same address")
+            }
+            StateError::ImplausibleWinnersCounter { counter, recorded }
+                if *counter < *recorded as u64 =>
+            {
+                write!(
+                    f,
+                    "winners_counter {} is below the {} recorded winner(s)",
+                    counter, recorded
+                )
+            }
+            StateError::ImplausibleWinnersCounter { counter, recorded } => write!(
+                f,
+                "winners_counter {} claims a wrapped history, but only {} of {} winner slots are filled",
+                counter, recorded, RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY
+            ),
+        }
+    }
+}
+
+impl core::error::Error for StateError {}
+
+/// Winner slot index past the end of the history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexError {
+    pub index: usize,
+    /// Number of slots in the history.
+    pub len: usize,
+}
+
+impl Display for IndexError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "winner slot {} is out of range (history has {} slots)",
+            self.index, self.len
+        )
+    }
+}
+
+impl core::error::Error for IndexError {}
+
+/// Operational values for the fields that only exist in `NewRL`.
+///
+/// Conversion leaves these zeroed; each `Some` replaces the corresponding
+/// field afterwards, while `None` keeps the zero default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct NewFieldOverrides {
+    pub schedule: Option<u8>,
+    pub draw_hour: Option<u8>,
+    /// Ticket price to apply from the next epoch (`next_epoch_data.new_price`).
+    pub next_price: Option<u64>,
+    /// Schedule to apply from the next epoch (`next_epoch_data.schedule`).
+    pub next_schedule: Option<u8>,
+}
+
+impl NewFieldOverrides {
+    /// Fills every value missing here from `fallback`.
+    pub fn or(self, fallback: NewFieldOverrides) -> NewFieldOverrides {
+        NewFieldOverrides {
+            schedule: self.schedule.or(fallback.schedule),
+            draw_hour: self.draw_hour.or(fallback.draw_hour),
+            next_price: self.next_price.or(fallback.next_price),
+            next_schedule: self.next_schedule.or(fallback.next_schedule),
+        }
+    }
+
+    /// Checks the provided hour and bitmasks with the same bounds as
+    /// `NewRL::validate_schedule`, including the deferred schedule.
+    pub fn validate(&self) -> Result<(), ScheduleError> {
+        if let Some(hour) = self.draw_hour.filter(|&hour| hour > 23) {
+            return Err(ScheduleError::InvalidDrawHour(hour));
+        }
+        if let Some(schedule) = self
+            .schedule
+            .filter(|&bits| !Schedule::from_bits(bits).is_valid())
+        {
+            return Err(ScheduleError::InvalidScheduleBits(schedule));
+        }
+        if let Some(schedule) = self
+            .next_schedule
+            .filter(|&bits| !Schedule::from_bits(bits).is_valid())
+        {
+            return Err(ScheduleError::InvalidNextScheduleBits(schedule));
+        }
+        Ok(())
+    }
+
+    /// Writes every provided value into `new_rl`.
+    pub fn apply_to(&self, new_rl: &mut NewRL) {
+        if let Some(schedule) = self.schedule {
+            new_rl.schedule = schedule;
+        }
+        if let Some(draw_hour) = self.draw_hour {
+            new_rl.draw_hour = draw_hour;
+        }
+        if let Some(new_price) = self.next_price {
+            new_rl.next_epoch_data.new_price = new_price;
+        }
+        if let Some(schedule) = self.next_schedule {
+            new_rl.next_epoch_data.schedule = schedule;
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for NewRL {
+    /// Stored fields in declaration order, with empty player and winner
+    /// slots omitted, followed by the derived `active_player_count` and
+    /// `winner_count` so they can be checked against `player_counter` and
+    /// `winners_counter` without re-deriving them.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let winners: Vec<&WinnerInfoNew> = self
+            .winners
+            .iter()
+            .filter(|w| !w.winner_address.is_zero())
+            .collect();
+        let players: Vec<&Id> = self.players_iter().collect();
+
+        let mut state = serializer.serialize_struct("NewRL", 20)?;
+        state.serialize_field("winners", &winners)?;
+        state.serialize_field("players", &players)?;
+        state.serialize_field("team_address", &self.team_address)?;
+        state.serialize_field("owner_address", &self.owner_address)?;
+        state.serialize_field("next_epoch_data", &self.next_epoch_data)?;
+        state.serialize_field("ticket_price", &self.ticket_price)?;
+        state.serialize_field("player_counter", &self.player_counter)?;
+        state.serialize_field("winners_counter", &self.winners_counter)?;
+        state.serialize_field("last_draw_day", &self.last_draw_day)?;
+        state.serialize_field("last_draw_hour", &self.last_draw_hour)?;
+        state.serialize_field("last_draw_date_stamp", &self.last_draw_date_stamp)?;
+        state.serialize_field("team_fee_percent", &self.team_fee_percent)?;
+        state.serialize_field("distribution_fee_percent", &self.distribution_fee_percent)?;
+        state.serialize_field("winner_fee_percent", &self.winner_fee_percent)?;
+        state.serialize_field("burn_percent", &self.burn_percent)?;
+        state.serialize_field("schedule", &self.schedule)?;
+        state.serialize_field("draw_hour", &self.draw_hour)?;
+        state.serialize_field("current_state", &self.current_state)?;
+        state.serialize_field("active_player_count", &players.len())?;
+        state.serialize_field("winner_count", &winners.len())?;
+        state.end()
+    }
+}
+
 impl From<&OldRL> for NewRL {
     fn from(old: &OldRL) -> Self {
+        NewRL::from_old(old)
+    }
+}
+
+/// Encoder for one `to_byte_sections` range.
+type SectionWriter = fn(&NewRL, &mut FieldWriter);
+
+impl NewRL {
+    /// Converts an old state.
+    ///
+    /// The old layout does not record when a winner was drawn: the tick
+    /// carries no wall-clock information and every epoch starts on the same
+    /// weekday, so `day_of_week` of the migrated winners stays 0.
+    ///
+    /// `player_counter` does not exist in the old layout either; it is
+    /// seeded with the number of migrated players, the tickets sold so far.
+    pub fn from_old(old: &OldRL) -> NewRL {
+        // Only occupied hash set slots carry players; freed slots may still
+        // hold a stale id. Both arrays hold RL_MAX_NUMBER_OF_PLAYERS ids, so
+        // no player can lack a slot.
+        let (new_rl, dropped) = NewRL::from_old_with_players(old, old.players.occupied_slots());
+        debug_assert_eq!(dropped, 0, "an OldRL player has no NewRL slot");
+        new_rl
+    }
+
+    /// Converts `old` like `from_old`, with the players taken from `slots`
+    /// instead of its hash set: the occupied `(slot, id)` pairs of a source
+    /// layout whose set may be larger than the NewRL players array.
+    ///
+    /// Players whose slot does not exist in NewRL are dropped; returns the
+    /// state and how many were dropped.
+    pub fn from_old_with_players<'a>(
+        old: &OldRL,
+        slots: impl IntoIterator<Item = (usize, &'a Id)>,
+    ) -> (NewRL, usize) {
         let mut new_rl = unsafe { MaybeUninit::<NewRL>::zeroed().assume_init() };
 
-        new_rl.winners = old
-            .winners
-            .iter()
-            .map(|w| WinnerInfoNew {
-                winner_address: w.winner_address,
-                revenue: w.revenue,
-                epoch: w.epoch,
-                tick: w.tick,
-                day_of_week: 0, // Default value for new field
-            })
-            .collect::<Vec<WinnerInfoNew>>()
-            .try_into()
-            .unwrap();
+        new_rl.winners = core::array::from_fn(|i| WinnerInfoNew::from(&old.winners[i]));
 
-        new_rl.players = old.players.players;
+        // Slot positions are kept, as in `PlayerHashSet::from_players`
+        let dropped = new_rl.place_players(slots);
         new_rl.team_address = old.team_address;
         new_rl.owner_address = old.owner_address;
         new_rl.ticket_price = old.ticket_price;
@@ -127,15 +567,694 @@ impl From<&OldRL> for NewRL {
         new_rl.current_state = old.current_state;
 
         new_rl.next_epoch_data = NextEpochData::default();
-        new_rl.player_counter = 0;
+        new_rl.player_counter = new_rl.active_player_count() as u64;
         new_rl.last_draw_day = 0;
         new_rl.last_draw_hour = 0;
         new_rl.last_draw_date_stamp = 0;
         new_rl.schedule = 0;
         new_rl.draw_hour = 0;
 
+        (new_rl, dropped)
+    }
+
+    /// Converts like `from_old_with_players`, but fails with
+    /// `ConvertError::PlayerCapacity` instead of dropping players.
+    pub fn try_from_old_with_players<'a>(
+        old: &OldRL,
+        slots: impl IntoIterator<Item = (usize, &'a Id)>,
+    ) -> Result<NewRL, ConvertError> {
+        match NewRL::from_old_with_players(old, slots) {
+            (new_rl, 0) => Ok(new_rl),
+            (_, dropped) => Err(ConvertError::PlayerCapacity {
+                dropped,
+                capacity: RL_MAX_NUMBER_OF_PLAYERS,
+            }),
+        }
+    }
+
+    /// Converts an old state like `from_old`, but keeps the `last_draw_*`
+    /// fields recorded in the draw-guard trailer of an extended old image
+    /// instead of zeroing them.
+    pub fn from_extended_old(old: &OldRL, guard: &DrawGuard) -> NewRL {
+        let mut new_rl = NewRL::from_old(old);
+        new_rl.last_draw_day = guard.last_draw_day;
+        new_rl.last_draw_hour = guard.last_draw_hour;
+        new_rl.last_draw_date_stamp = guard.last_draw_date_stamp;
         new_rl
     }
+
+    /// Decodes a `NewRL` from its on-disk byte image.
+    ///
+    /// Fields are read explicitly at their `repr(C)` offsets, mirroring
+    /// `OldRL::from_bytes`, so an invalid `current_state` byte is an error.
+    pub fn from_bytes(buf: &[u8]) -> Result<NewRL, ConvertError> {
+        let reader = FieldReader::new(buf, NEW_RL_SIZE)?;
+
+        let winners = core::array::from_fn(|i| {
+            let base = offset_of!(NewRL, winners) + i * size_of::<WinnerInfoNew>();
+            WinnerInfoNew {
+                winner_address: reader.id(base + offset_of!(WinnerInfoNew, winner_address)),
+                revenue: reader.u64(base + offset_of!(WinnerInfoNew, revenue)),
+                tick: reader.u32(base + offset_of!(WinnerInfoNew, tick)),
+                epoch: reader.u16(base + offset_of!(WinnerInfoNew, epoch)),
+                day_of_week: reader.u8(base + offset_of!(WinnerInfoNew, day_of_week)),
+            }
+        });
+        let players =
+            core::array::from_fn(|i| reader.id(offset_of!(NewRL, players) + i * size_of::<Id>()));
+        let next_epoch_base = offset_of!(NewRL, next_epoch_data);
+        let next_epoch_data = NextEpochData {
+            new_price: reader.u64(next_epoch_base + offset_of!(NextEpochData, new_price)),
+            schedule: reader.u8(next_epoch_base + offset_of!(NextEpochData, schedule)),
+        };
+
+        // Start from zeroed memory so padding stays zero, as in `From<&OldRL>`
+        let mut new_rl = unsafe { MaybeUninit::<NewRL>::zeroed().assume_init() };
+        new_rl.winners = winners;
+        new_rl.players = players;
+        new_rl.team_address = reader.id(offset_of!(NewRL, team_address));
+        new_rl.owner_address = reader.id(offset_of!(NewRL, owner_address));
+        new_rl.next_epoch_data = next_epoch_data;
+        new_rl.ticket_price = reader.u64(offset_of!(NewRL, ticket_price));
+        new_rl.player_counter = reader.u64(offset_of!(NewRL, player_counter));
+        new_rl.winners_counter = reader.u64(offset_of!(NewRL, winners_counter));
+        new_rl.last_draw_day = reader.u8(offset_of!(NewRL, last_draw_day));
+        new_rl.last_draw_hour = reader.u8(offset_of!(NewRL, last_draw_hour));
+        new_rl.last_draw_date_stamp = reader.u32(offset_of!(NewRL, last_draw_date_stamp));
+        new_rl.team_fee_percent = reader.u8(offset_of!(NewRL, team_fee_percent));
+        new_rl.distribution_fee_percent = reader.u8(offset_of!(NewRL, distribution_fee_percent));
+        new_rl.winner_fee_percent = reader.u8(offset_of!(NewRL, winner_fee_percent));
+        new_rl.burn_percent = reader.u8(offset_of!(NewRL, burn_percent));
+        new_rl.schedule = reader.u8(offset_of!(NewRL, schedule));
+        new_rl.draw_hour = reader.u8(offset_of!(NewRL, draw_hour));
+        new_rl.current_state = reader.state(offset_of!(NewRL, current_state))?;
+
+        Ok(new_rl)
+    }
+
+    /// Encodes the structure into its on-disk byte image.
+    ///
+    /// Fields are written at their `repr(C)` offsets with little-endian
+    /// integers; padding bytes are always zero.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = FieldWriter::new(NEW_RL_SIZE);
+        self.write_winners(&mut writer);
+        self.write_players(&mut writer);
+        self.write_fields(&mut writer);
+        writer.into_bytes()
+    }
+
+    /// Encodes the image in three consecutive sections (winners, players,
+    /// remaining fields), each yielding the same bytes as the matching range
+    /// of `to_bytes`, so a writer never needs the whole image at once.
+    pub fn to_byte_sections(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        let players = offset_of!(NewRL, players);
+        let fields = offset_of!(NewRL, team_address);
+        let sections: [(usize, usize, SectionWriter); 3] = [
+            (0, players, NewRL::write_winners),
+            (players, fields, NewRL::write_players),
+            (fields, NEW_RL_SIZE, NewRL::write_fields),
+        ];
+        sections.into_iter().map(move |(start, end, write)| {
+            let mut writer = FieldWriter::window(start, end - start);
+            write(self, &mut writer);
+            writer.into_bytes()
+        })
+    }
+
+    /// Reads and decodes a `NewRL` image from any `std::io` reader, consuming
+    /// at most one byte past `NEW_RL_SIZE` (see `OldRL::from_reader`).
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: std::io::Read>(reader: &mut R) -> Result<NewRL, ConvertError> {
+        NewRL::from_bytes(&crate::common::read_image(reader, NEW_RL_SIZE)?)
+    }
+
+    /// Writes the byte image to any `std::io` writer one section at a time,
+    /// the blocking counterpart of `write_new_rl_to`, and returns the number
+    /// of bytes written. The writer is not flushed.
+    #[cfg(feature = "std")]
+    pub fn to_writer<W: std::io::Write>(&self, writer: &mut W) -> Result<usize, ConvertError> {
+        let mut written = 0;
+        for section in self.to_byte_sections() {
+            writer.write_all(&section)?;
+            written += section.len();
+        }
+        Ok(written)
+    }
+
+    fn write_winners(&self, writer: &mut FieldWriter) {
+        for (i, w) in self.winners.iter().enumerate() {
+            let base = offset_of!(NewRL, winners) + i * size_of::<WinnerInfoNew>();
+            writer.id(
+                base + offset_of!(WinnerInfoNew, winner_address),
+                &w.winner_address,
+            );
+            writer.u64(base + offset_of!(WinnerInfoNew, revenue), w.revenue);
+            writer.u32(base + offset_of!(WinnerInfoNew, tick), w.tick);
+            writer.u16(base + offset_of!(WinnerInfoNew, epoch), w.epoch);
+            writer.u8(base + offset_of!(WinnerInfoNew, day_of_week), w.day_of_week);
+        }
+    }
+
+    fn write_players(&self, writer: &mut FieldWriter) {
+        for (i, player) in self.players.iter().enumerate() {
+            writer.id(offset_of!(NewRL, players) + i * size_of::<Id>(), player);
+        }
+    }
+
+    /// Everything after the players array, from `team_address` on.
+    fn write_fields(&self, writer: &mut FieldWriter) {
+        let next_epoch_base = offset_of!(NewRL, next_epoch_data);
+        writer.u64(
+            next_epoch_base + offset_of!(NextEpochData, new_price),
+            self.next_epoch_data.new_price,
+        );
+        writer.u8(
+            next_epoch_base + offset_of!(NextEpochData, schedule),
+            self.next_epoch_data.schedule,
+        );
+
+        writer.id(offset_of!(NewRL, team_address), &self.team_address);
+        writer.id(offset_of!(NewRL, owner_address), &self.owner_address);
+        writer.u64(offset_of!(NewRL, ticket_price), self.ticket_price);
+        writer.u64(offset_of!(NewRL, player_counter), self.player_counter);
+        writer.u64(offset_of!(NewRL, winners_counter), self.winners_counter);
+        writer.u8(offset_of!(NewRL, last_draw_day), self.last_draw_day);
+        writer.u8(offset_of!(NewRL, last_draw_hour), self.last_draw_hour);
+        writer.u32(
+            offset_of!(NewRL, last_draw_date_stamp),
+            self.last_draw_date_stamp,
+        );
+        writer.u8(offset_of!(NewRL, team_fee_percent), self.team_fee_percent);
+        writer.u8(
+            offset_of!(NewRL, distribution_fee_percent),
+            self.distribution_fee_percent,
+        );
+        writer.u8(
+            offset_of!(NewRL, winner_fee_percent),
+            self.winner_fee_percent,
+        );
+        writer.u8(offset_of!(NewRL, burn_percent), self.burn_percent);
+        writer.u8(offset_of!(NewRL, schedule), self.schedule);
+        writer.u8(offset_of!(NewRL, draw_hour), self.draw_hour);
+        writer.u8(offset_of!(NewRL, current_state), self.current_state as u8);
+    }
+
+    /// Recorded winners from oldest to newest, skipping empty slots.
+    ///
+    /// `winners_counter` points at the next slot to overwrite, so once the
+    /// ring buffer has wrapped the oldest entry sits right after it.
+    pub fn winners_chronological(&self) -> impl Iterator<Item = &WinnerInfoNew> {
+        let start = (self.winners_counter % RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY as u64) as usize;
+        self.winners[start..]
+            .iter()
+            .chain(self.winners[..start].iter())
+            .filter(|w| !w.winner_address.is_zero())
+    }
+
+    /// Recorded winners with `start <= epoch <= end`, oldest first.
+    ///
+    /// Yields nothing when `start > end`.
+    pub fn winners_in_epochs(&self, start: u16, end: u16) -> impl Iterator<Item = &WinnerInfoNew> {
+        self.winners_chronological()
+            .filter(move |w| (start..=end).contains(&w.epoch))
+    }
+
+    /// Physical slots of recorded winners that are older, by `(epoch, tick)`,
+    /// than the recorded winner right before them in ring order; empty when
+    /// the history is in draw order and `winners_chronological` can be
+    /// trusted.
+    pub fn winners_out_of_order(&self) -> Vec<usize> {
+        let start = (self.winners_counter % RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY as u64) as usize;
+        let mut previous = None;
+        let mut slots = Vec::new();
+        for i in (start..self.winners.len()).chain(0..start) {
+            let winner = &self.winners[i];
+            if winner.winner_address.is_zero() {
+                continue;
+            }
+            let key = (winner.epoch, winner.tick);
+            if previous.is_some_and(|previous| key < previous) {
+                slots.push(i);
+            }
+            previous = Some(key);
+        }
+        slots
+    }
+
+    /// Rewrites the recorded winners in `(epoch, tick)` order, oldest in
+    /// slot 0, and resets `winners_counter` to their number.
+    ///
+    /// Entries of the same draw keep their ring order. Slots without an
+    /// address are dropped, and a counter that had passed the history
+    /// size loses the draws the ring no longer held.
+    pub fn sort_winners(&mut self) {
+        let mut recorded: Vec<WinnerInfoNew> = self.winners_chronological().copied().collect();
+        recorded.sort_by_key(|winner| (winner.epoch, winner.tick));
+        self.winners = [WinnerInfoNew::default(); RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY];
+        self.winners[..recorded.len()].copy_from_slice(&recorded);
+        self.winners_counter = recorded.len() as u64;
+    }
+
+    /// Empties the winner slot at physical position `index`.
+    ///
+    /// `winners_counter` is left alone, so the next draw still writes to the
+    /// same slot; `winners_chronological` simply skips the emptied entry and
+    /// the remaining winners keep their order.
+    pub fn clear_winner(&mut self, index: usize) -> Result<(), IndexError> {
+        let len = self.winners.len();
+        let slot = self
+            .winners
+            .get_mut(index)
+            .ok_or(IndexError { index, len })?;
+        *slot = WinnerInfoNew::default();
+        Ok(())
+    }
+
+    /// Moves the recorded winners to the front of the history, oldest first,
+    /// and sets `winners_counter` to their number.
+    ///
+    /// `winners_chronological` yields the same entries in the same order
+    /// before and after. The total count of draws held by a wrapped counter
+    /// is lost.
+    pub fn compact_winners(&mut self) {
+        let mut compacted = [WinnerInfoNew::default(); RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY];
+        let mut count = 0;
+        for (slot, winner) in compacted.iter_mut().zip(self.winners_chronological()) {
+            *slot = *winner;
+            count += 1;
+        }
+        self.winners = compacted;
+        self.winners_counter = count;
+    }
+
+    /// Copy of this state whose winners history holds the recorded winners
+    /// of both `self` and `other`.
+    ///
+    /// Winners are ordered by epoch and tick, entries present in both
+    /// histories are kept once, and the result is written into the ring
+    /// buffer the way the contract would record it, so on overflow only the
+    /// most recent `RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY` remain.
+    /// `winners_counter` becomes the number of distinct winners. Every other
+    /// field is taken from `self`.
+    pub fn merge_winners(&self, other: &NewRL) -> NewRL {
+        let mut winners: Vec<WinnerInfoNew> = self
+            .winners_chronological()
+            .chain(other.winners_chronological())
+            .copied()
+            .collect();
+        // A full key puts identical entries next to each other for dedup
+        winners.sort_by_key(|w| {
+            (
+                w.epoch,
+                w.tick,
+                w.winner_address.data,
+                w.revenue,
+                w.day_of_week,
+            )
+        });
+        winners.dedup();
+
+        let mut merged = *self;
+        merged.winners = [WinnerInfoNew::default(); RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY];
+        for (i, winner) in winners.iter().enumerate() {
+            merged.winners[i % RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY] = *winner;
+        }
+        merged.winners_counter = winners.len() as u64;
+        merged
+    }
+
+    /// Physical indices of winner slots that look corrupt: an address with
+    /// zero revenue (every draw pays out) or revenue without an address.
+    pub fn inconsistent_winner_slots(&self) -> Vec<usize> {
+        self.winners
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| w.winner_address.is_zero() != (w.revenue == 0))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Whether more winners were recorded than the history holds.
+    ///
+    /// Once `winners_counter` reaches `RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY`
+    /// the ring buffer has wrapped and only the latest winners are kept.
+    pub fn has_wrapped(&self) -> bool {
+        self.winners_counter >= RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY as u64
+    }
+
+    /// Compares the persistent contract data, ignoring the draw guard.
+    ///
+    /// `last_draw_day`, `last_draw_hour` and `last_draw_date_stamp` only
+    /// record when the last draw ran and legitimately differ between runs,
+    /// so they are excluded. Every other field is compared, including
+    /// `next_epoch_data` and `current_state`.
+    pub fn semantic_eq(&self, other: &NewRL) -> bool {
+        self.team_address == other.team_address
+            && self.owner_address == other.owner_address
+            && self.team_fee_percent == other.team_fee_percent
+            && self.distribution_fee_percent == other.distribution_fee_percent
+            && self.winner_fee_percent == other.winner_fee_percent
+            && self.burn_percent == other.burn_percent
+            && self.ticket_price == other.ticket_price
+            && self.player_counter == other.player_counter
+            && self.winners_counter == other.winners_counter
+            && self.schedule == other.schedule
+            && self.draw_hour == other.draw_hour
+            && self.next_epoch_data == other.next_epoch_data
+            && self.current_state == other.current_state
+            && self.players == other.players
+            && self.winners == other.winners
+    }
+
+    /// KangarooTwelve digest of the canonical `to_bytes` image.
+    ///
+    /// Padding is written as zeros, so two states with equal fields always
+    /// hash equal, whatever their in-memory padding holds.
+    pub fn content_hash(&self) -> [u8; 32] {
+        let mut hasher = K12Hasher::default();
+        for section in self.to_byte_sections() {
+            hasher.update(&section);
+        }
+        hasher.finalize()
+    }
+
+    /// Sum of `revenue` over all recorded winners.
+    ///
+    /// Accumulated in `u128`, which cannot overflow even with every slot
+    /// holding `u64::MAX`.
+    pub fn total_revenue(&self) -> u128 {
+        self.winners_chronological()
+            .map(|w| u128::from(w.revenue))
+            .sum()
+    }
+
+    /// Copies `(slot, id)` pairs into `players`, keeping each player's slot.
+    ///
+    /// Slots past the end of `players` are skipped; returns how many
+    /// players were dropped that way.
+    pub fn place_players<'a>(&mut self, slots: impl IntoIterator<Item = (usize, &'a Id)>) -> usize {
+        let mut dropped = 0;
+        for (i, player) in slots {
+            match self.players.get_mut(i) {
+                Some(slot) => *slot = *player,
+                None => dropped += 1,
+            }
+        }
+        dropped
+    }
+
+    /// Active players in slot order, skipping empty (zero) slots.
+    pub fn players_iter(&self) -> impl Iterator<Item = &Id> {
+        self.players.iter().filter(|p| !p.is_zero())
+    }
+
+    /// Number of non-zero slots in `players`.
+    ///
+    /// `player_counter` is maintained separately by the contract and may
+    /// disagree with this after a conversion or a partial update.
+    pub fn active_player_count(&self) -> usize {
+        self.players_iter().count()
+    }
+
+    /// Checks that team, distribution, burn and winner fees form a 100% split.
+    ///
+    /// The winner share is documented as the "auto remainder", so a valid
+    /// state always has it equal to 100 minus the other three percentages.
+    pub fn validate_fees(&self) -> Result<(), FeeError> {
+        let fees = FeeError {
+            team: self.team_fee_percent,
+            distribution: self.distribution_fee_percent,
+            burn: self.burn_percent,
+            winner: self.winner_fee_percent,
+        };
+        if fees.total() == 100 {
+            Ok(())
+        } else {
+            Err(fees)
+        }
+    }
+}
+
+/// Reason a `NewRLBuilder` refused to build a state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    InvalidFees(FeeError),
+    InvalidSchedule(ScheduleError),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::InvalidFees(e) => write!(f, "invalid fees: {}", e),
+            BuildError::InvalidSchedule(e) => write!(f, "invalid schedule: {}", e),
+        }
+    }
+}
+
+impl core::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            BuildError::InvalidFees(e) => Some(e),
+            BuildError::InvalidSchedule(e) => Some(e),
+        }
+    }
+}
+
+impl From<FeeError> for BuildError {
+    fn from(e: FeeError) -> Self {
+        BuildError::InvalidFees(e)
+    }
+}
+
+impl From<ScheduleError> for BuildError {
+    fn from(e: ScheduleError) -> Self {
+        BuildError::InvalidSchedule(e)
+    }
+}
+
+/// Fluent constructor for a fresh `NewRL`.
+///
+/// Fields without a setter, and setters that are never called, keep the
+/// zeroed `NewRL::default()` values. `build` runs the same fee and schedule
+/// checks as the converter, so the four fee percentages must be set to a
+/// 100% split.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NewRLBuilder {
+    team_address: Id,
+    owner_address: Id,
+    team_fee_percent: u8,
+    distribution_fee_percent: u8,
+    winner_fee_percent: u8,
+    burn_percent: u8,
+    ticket_price: u64,
+    schedule: u8,
+    draw_hour: u8,
+    next_epoch_data: NextEpochData,
+    current_state: EState,
+}
+
+impl NewRLBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn team_address(mut self, address: Id) -> Self {
+        self.team_address = address;
+        self
+    }
+
+    pub fn owner_address(mut self, address: Id) -> Self {
+        self.owner_address = address;
+        self
+    }
+
+    pub fn team_fee_percent(mut self, percent: u8) -> Self {
+        self.team_fee_percent = percent;
+        self
+    }
+
+    pub fn distribution_fee_percent(mut self, percent: u8) -> Self {
+        self.distribution_fee_percent = percent;
+        self
+    }
+
+    pub fn winner_fee_percent(mut self, percent: u8) -> Self {
+        self.winner_fee_percent = percent;
+        self
+    }
+
+    pub fn burn_percent(mut self, percent: u8) -> Self {
+        self.burn_percent = percent;
+        self
+    }
+
+    pub fn ticket_price(mut self, price: u64) -> Self {
+        self.ticket_price = price;
+        self
+    }
+
+    /// Weekday bitmask, bit 0 = Wednesday ... bit 6 = Tuesday.
+    pub fn schedule(mut self, schedule: u8) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// UTC hour of the draw, `0..=23`.
+    pub fn draw_hour(mut self, hour: u8) -> Self {
+        self.draw_hour = hour;
+        self
+    }
+
+    /// Price and schedule deferred to the next epoch.
+    pub fn next_epoch_data(mut self, next_epoch_data: NextEpochData) -> Self {
+        self.next_epoch_data = next_epoch_data;
+        self
+    }
+
+    pub fn current_state(mut self, state: EState) -> Self {
+        self.current_state = state;
+        self
+    }
+
+    /// Validates the fee split and schedule, then returns the state.
+    pub fn build(self) -> Result<NewRL, BuildError> {
+        let new_rl = NewRL {
+            team_address: self.team_address,
+            owner_address: self.owner_address,
+            team_fee_percent: self.team_fee_percent,
+            distribution_fee_percent: self.distribution_fee_percent,
+            winner_fee_percent: self.winner_fee_percent,
+            burn_percent: self.burn_percent,
+            ticket_price: self.ticket_price,
+            schedule: self.schedule,
+            draw_hour: self.draw_hour,
+            next_epoch_data: self.next_epoch_data,
+            current_state: self.current_state,
+            ..NewRL::default()
+        };
+
+        new_rl.validate_fees()?;
+        new_rl.validate_schedule()?;
+        Ok(new_rl)
+    }
+}
+
+impl NewRL {
+    /// Starts a `NewRLBuilder` with all fields zeroed.
+    pub fn builder() -> NewRLBuilder {
+        NewRLBuilder::new()
+    }
+
+    /// Checks that `draw_hour` is a valid UTC hour and that `schedule` and
+    /// `next_epoch_data.schedule` only use weekday bits.
+    pub fn validate_schedule(&self) -> Result<(), ScheduleError> {
+        if self.draw_hour > 23 {
+            return Err(ScheduleError::InvalidDrawHour(self.draw_hour));
+        }
+        if !self.schedule_days().is_valid() {
+            return Err(ScheduleError::InvalidScheduleBits(self.schedule));
+        }
+        self.next_epoch_data.validate()
+    }
+
+    /// Simulates an epoch rollover: the deferred `next_epoch_data` price and
+    /// schedule become the current ones and the deferred data is cleared.
+    ///
+    /// Values are moved over as stored, zeros included; nothing else
+    /// (players, winners, counters, draw guard) is touched. An invalid
+    /// deferred schedule is rejected and leaves the state unchanged.
+    pub fn apply_next_epoch(&mut self) -> Result<(), ScheduleError> {
+        self.next_epoch_data.validate()?;
+        self.ticket_price = self.next_epoch_data.new_price;
+        self.schedule = self.next_epoch_data.schedule;
+        self.next_epoch_data = NextEpochData::default();
+        Ok(())
+    }
+
+    /// Records a drawn winner as the contract does: into the ring buffer
+    /// slot `winners_counter` points at, overwriting the oldest entry once
+    /// the history is full, then advances the counter.
+    pub fn record_winner(&mut self, winner: WinnerInfoNew) {
+        let slot = (self.winners_counter % RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY as u64) as usize;
+        self.winners[slot] = winner;
+        self.winners_counter += 1;
+    }
+
+    /// Simulates a full epoch boundary: the deferred price and schedule are
+    /// applied (see `apply_next_epoch`), every player slot is cleared,
+    /// `player_counter` is reset and `winner`, if any, is recorded with
+    /// `record_winner`.
+    ///
+    /// An invalid deferred schedule is rejected before anything changes.
+    pub fn simulate_rollover(
+        &mut self,
+        winner: Option<WinnerInfoNew>,
+    ) -> Result<(), ScheduleError> {
+        self.apply_next_epoch()?;
+        self.players = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+        self.player_counter = 0;
+        if let Some(winner) = winner {
+            self.record_winner(winner);
+        }
+        Ok(())
+    }
+
+    /// `schedule` as a typed weekday set.
+    pub fn schedule_days(&self) -> Schedule {
+        Schedule::from_bits(self.schedule)
+    }
+
+    /// Checks that `current_state` fits the rest of the state: a selling
+    /// lottery must charge for tickets, and a locked one must have no players left.
+    pub fn validate_state(&self) -> Result<(), StateError> {
+        match self.current_state {
+            EState::Selling if self.ticket_price == 0 => Err(StateError::SellingWithZeroPrice),
+            EState::Locked if self.active_player_count() > 0 => {
+                Err(StateError::LockedWithPlayers(self.active_player_count()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that `winners_counter` fits the recorded winners: it counts
+    /// every draw, so it cannot be below the number of filled slots, and a
+    /// counter past the history size means the ring has wrapped and should
+    /// be full. Garbage in the counter of a full history goes unnoticed.
+    pub fn validate_winners_counter(&self) -> Result<(), StateError> {
+        let recorded = self.winners_chronological().count();
+        let capacity = RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY as u64;
+        let counter = self.winners_counter;
+        if counter < recorded as u64 || (counter > capacity && recorded < capacity as usize) {
+            return Err(StateError::ImplausibleWinnersCounter { counter, recorded });
+        }
+        Ok(())
+    }
+
+    /// Checks that the owner and the team fee recipient are different
+    /// addresses; two zero addresses are left to `zero_addresses_while_selling`.
+    pub fn validate_addresses(&self) -> Result<(), StateError> {
+        if self.owner_address == self.team_address && !self.owner_address.is_zero() {
+            return Err(StateError::OwnerIsTeam);
+        }
+        Ok(())
+    }
+
+    /// Names of the owner and team addresses that are zero while tickets
+    /// are on sale; empty when the contract is locked.
+    pub fn zero_addresses_while_selling(&self) -> Vec<&'static str> {
+        if self.current_state != EState::Selling {
+            return Vec::new();
+        }
+        [
+            ("owner_address", &self.owner_address),
+            ("team_address", &self.team_address),
+        ]
+        .into_iter()
+        .filter(|(_, id)| id.is_zero())
+        .map(|(name, _)| name)
+        .collect()
+    }
 }
 
 impl Default for NewRL {
@@ -146,74 +1265,874 @@ impl Default for NewRL {
     }
 }
 
+impl Hash for NewRL {
+    /// Feeds `content_hash`, consistent with the field-wise `Eq`.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.content_hash().hash(state);
+    }
+}
+
 impl Display for NewRL {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        writeln!(
+        self.fmt_with_config(f, &DisplayConfig::default())
+    }
+}
+
+impl NewRL {
+    /// Writes the `Display` dump with `═` separators of `width` characters
+    /// and the title centered between them.
+    pub fn fmt_with_width(&self, f: &mut Formatter<'_>, width: usize) -> fmt::Result {
+        self.fmt_with_config(
             f,
-            "\n═══════════════════════════════════════════════════════════"
-        )?;
-        writeln!(f, "           NewRL STRUCTURE CONTENTS")?;
+            &DisplayConfig {
+                width,
+                ..DisplayConfig::default()
+            },
+        )
+    }
+
+    /// Writes the `Display` dump rendered as described by `config`.
+    pub fn fmt_with_config(&self, f: &mut Formatter<'_>, config: &DisplayConfig) -> fmt::Result {
+        let width = config.width;
+        let separator = "═".repeat(width);
+        let bad_fees = self.validate_fees().is_err();
+        let shared = self.validate_addresses().is_err();
+        let zero = self.zero_addresses_while_selling();
+        let team_alert = shared || zero.contains(&"team_address");
+        let owner_alert = shared || zero.contains(&"owner_address");
+        writeln!(f, "\n{}", separator)?;
         writeln!(
             f,
-            "═══════════════════════════════════════════════════════════\n"
+            "{}",
+            config.header(centered("NewRL STRUCTURE CONTENTS", width))
         )?;
+        writeln!(f, "{}\n", separator)?;
 
         // Addresses
-        writeln!(f, "📍 ADDRESSES:")?;
-        writeln!(f, "  Team Address:  {}", self.team_address)?;
-        writeln!(f, "  Owner Address: {}", self.owner_address)?;
+        writeln!(f, "{}", config.header("📍 ADDRESSES:"))?;
+        writeln!(
+            f,
+            "  Team Address:  {}",
+            config.alert(team_alert, config.id("team", &self.team_address))
+        )?;
+        writeln!(
+            f,
+            "  Owner Address: {}",
+            config.alert(owner_alert, config.id("owner", &self.owner_address))
+        )?;
 
         // Fees
-        writeln!(f, "\n💰 FEES:")?;
-        writeln!(f, "  Team:          {}%", self.team_fee_percent)?;
-        writeln!(f, "  Distribution:  {}%", self.distribution_fee_percent)?;
-        writeln!(f, "  Winner:        {}%", self.winner_fee_percent)?;
-        writeln!(f, "  Burn:          {}%", self.burn_percent)?;
+        writeln!(f, "\n{}", config.header("💰 FEES:"))?;
+        writeln!(
+            f,
+            "  Team:          {}%",
+            config.alert(bad_fees, self.team_fee_percent)
+        )?;
+        writeln!(
+            f,
+            "  Distribution:  {}%",
+            config.alert(bad_fees, self.distribution_fee_percent)
+        )?;
+        writeln!(
+            f,
+            "  Winner:        {}%",
+            config.alert(bad_fees, self.winner_fee_percent)
+        )?;
+        writeln!(
+            f,
+            "  Burn:          {}%",
+            config.alert(bad_fees, self.burn_percent)
+        )?;
 
         // Ticket price
-        writeln!(f, "\n🎫 TICKETS:")?;
+        writeln!(f, "\n{}", config.header("🎫 TICKETS:"))?;
         writeln!(f, "  Ticket price:  {} units", self.ticket_price)?;
 
         // Counters and schedule
-        writeln!(f, "\n📊 COUNTERS:")?;
+        writeln!(f, "\n{}", config.header("📊 COUNTERS:"))?;
         writeln!(f, "  Players (tickets sold): {}", self.player_counter)?;
         writeln!(f, "  Winners in history:     {}", self.winners_counter)?;
 
-        writeln!(f, "\n🕒 SCHEDULE:")?;
+        writeln!(f, "\n{}", config.header("🕒 SCHEDULE:"))?;
         writeln!(f, "  Schedule bitmask: 0b{:08b}", self.schedule)?;
+        writeln!(f, "  Schedule: {}", self.schedule_days())?;
         writeln!(f, "  Draw hour (UTC): {}", self.draw_hour)?;
-        writeln!(f, "  Last draw day: {}", self.last_draw_day.to_string())?;
-        writeln!(f, "  Last draw hour: {}", self.last_draw_hour.to_string())?;
+        writeln!(f, "  Last draw day: {}", self.last_draw_day)?;
+        writeln!(f, "  Last draw hour: {}", self.last_draw_hour)?;
         writeln!(f, "  Last draw date stamp: {}", self.last_draw_date_stamp)?;
+        writeln!(f, "  Next epoch: {}", self.next_epoch_data)?;
 
         // Players
-        writeln!(f, "\n👥 PLAYERS:")?;
+        writeln!(f, "\n{}", config.header("👥 PLAYERS:"))?;
+        writeln!(f, "  Active players: {}", self.active_player_count())?;
         writeln!(f, "  Players list:")?;
-        for (i, player) in self.players.iter().enumerate() {
-            if !player.is_zero() {
-                writeln!(f, "    {}. {}", i + 1, player)?;
-            }
+        let players = self
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| !p.is_zero());
+        for (i, player) in players.clone().take(config.shown()) {
+            writeln!(f, "    {}. {}", i + 1, config.id("player", player))?;
         }
+        config.write_remainder(f, players.count())?;
 
         // Winners
-        writeln!(f, "\n🏆 WINNERS HISTORY:")?;
-        writeln!(f, "  Winners list:")?;
-        for (i, winner) in self.winners.iter().enumerate() {
-            if !winner.winner_address.is_zero() {
-                writeln!(f, "    {}. Address: {}", i + 1, winner.winner_address)?;
-                writeln!(f, "       Prize:   {} units", winner.revenue)?;
-                writeln!(f, "       Epoch: {}, Tick: {}", winner.epoch, winner.tick)?;
-                writeln!(f, "       Day of week: {}", winner.day_of_week)?;
-            }
+        writeln!(f, "\n{}", config.header("🏆 WINNERS HISTORY:"))?;
+        writeln!(f, "  Winners list (oldest first):")?;
+        for (i, winner) in self
+            .winners_chronological()
+            .enumerate()
+            .take(config.shown())
+        {
+            write!(f, "    {}. ", i + 1)?;
+            winner.fmt_with_config(f, config)?;
+            writeln!(f)?;
         }
+        config.write_remainder(f, self.winners_chronological().count())?;
 
         // State
-        writeln!(f, "\n⚙️  STATE:")?;
-        writeln!(f, "  Current state: {:?}", self.current_state)?;
-
+        writeln!(f, "\n{}", config.header("⚙️  STATE:"))?;
         writeln!(
             f,
-            "\n═══════════════════════════════════════════════════════════"
+            "  Current state: {}",
+            config.alert(self.validate_state().is_err(), self.current_state.as_str())
+        )?;
+
+        writeln!(f, "\n{}", separator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::old_rl::PlayerHashSet;
+    use alloc::boxed::Box;
+    use alloc::string::ToString;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    fn id() -> impl Strategy<Value = Id> {
+        any::<[u8; 32]>().prop_map(|data| Id { data })
+    }
+
+    fn winner() -> impl Strategy<Value = WinnerInfo> {
+        (id(), any::<u64>(), any::<u16>(), any::<u32>()).prop_map(
+            |(winner_address, revenue, epoch, tick)| WinnerInfo {
+                winner_address,
+                revenue,
+                epoch,
+                tick,
+            },
         )
     }
+
+    /// Team, distribution, burn and winner fees adding up to 100.
+    fn fees() -> impl Strategy<Value = [u8; 4]> {
+        (0..=100u8)
+            .prop_flat_map(|team| (Just(team), 0..=100 - team))
+            .prop_flat_map(|(team, distribution)| {
+                (
+                    Just(team),
+                    Just(distribution),
+                    0..=100 - team - distribution,
+                )
+            })
+            .prop_map(|(team, distribution, burn)| {
+                [team, distribution, burn, 100 - team - distribution - burn]
+            })
+    }
+
+    prop_compose! {
+        /// An OldRL with random addresses, fees and winners, some occupied
+        /// player slots and some stale ids left in freed slots.
+        fn old_rl()(
+            team_address in id(),
+            owner_address in id(),
+            [team, distribution, burn, winner_fee] in fees(),
+            ticket_price in any::<u64>(),
+            players in vec((0..RL_MAX_NUMBER_OF_PLAYERS, id()), 0..64),
+            stale in vec((0..RL_MAX_NUMBER_OF_PLAYERS, id()), 0..8),
+            winners in vec((0..RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY, winner()), 0..64),
+            next_index in 0..RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY as u64,
+            selling in any::<bool>(),
+        ) -> Box<OldRL> {
+            let mut slots = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+            for (i, player) in players {
+                slots[i] = player;
+            }
+            let mut players = PlayerHashSet::from_players(&slots);
+            for (i, player) in stale {
+                if !players.is_occupied(i) {
+                    players.players[i] = player;
+                }
+            }
+
+            let mut history = [WinnerInfo::default(); RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY];
+            for (i, winner) in winners {
+                history[i] = winner;
+            }
+
+            Box::new(OldRL {
+                team_address,
+                owner_address,
+                team_fee_percent: team,
+                distribution_fee_percent: distribution,
+                winner_fee_percent: winner_fee,
+                burn_percent: burn,
+                ticket_price,
+                players,
+                winners: history,
+                winners_info_next_empty_index: next_index,
+                current_state: if selling { EState::Selling } else { EState::Locked },
+            })
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn conversion_carries_shared_fields_over(old in old_rl()) {
+            let new_rl = NewRL::from_old(&old);
+
+            prop_assert_eq!(new_rl.team_address, old.team_address);
+            prop_assert_eq!(new_rl.owner_address, old.owner_address);
+            prop_assert_eq!(new_rl.team_fee_percent, old.team_fee_percent);
+            prop_assert_eq!(new_rl.distribution_fee_percent, old.distribution_fee_percent);
+            prop_assert_eq!(new_rl.winner_fee_percent, old.winner_fee_percent);
+            prop_assert_eq!(new_rl.burn_percent, old.burn_percent);
+            prop_assert_eq!(new_rl.ticket_price, old.ticket_price);
+            prop_assert_eq!(new_rl.current_state, old.current_state);
+            prop_assert_eq!(new_rl.winners_counter, old.winners_info_next_empty_index);
+
+            for (new, old) in new_rl.winners.iter().zip(&old.winners) {
+                prop_assert_eq!(new.winner_address, old.winner_address);
+                prop_assert_eq!(new.revenue, old.revenue);
+                prop_assert_eq!(new.epoch, old.epoch);
+                prop_assert_eq!(new.tick, old.tick);
+                prop_assert_eq!(new.day_of_week, 0);
+            }
+        }
+
+        #[test]
+        fn occupied_players_keep_their_slots(old in old_rl()) {
+            let new_rl = NewRL::from_old(&old);
+
+            for (i, player) in new_rl.players.iter().enumerate() {
+                if old.players.is_occupied(i) {
+                    prop_assert_eq!(*player, old.players.players[i]);
+                } else {
+                    prop_assert!(player.is_zero(), "stale id copied into slot {}", i);
+                }
+            }
+        }
+
+        #[test]
+        fn new_only_fields_are_zero(old in old_rl()) {
+            let new_rl = NewRL::from_old(&old);
+
+            prop_assert_eq!(new_rl.next_epoch_data, NextEpochData::default());
+            prop_assert_eq!(new_rl.player_counter, new_rl.active_player_count() as u64);
+            prop_assert_eq!(new_rl.last_draw_day, 0);
+            prop_assert_eq!(new_rl.last_draw_hour, 0);
+            prop_assert_eq!(new_rl.last_draw_date_stamp, 0);
+            prop_assert_eq!(new_rl.schedule, 0);
+            prop_assert_eq!(new_rl.draw_hour, 0);
+        }
+
+        #[test]
+        fn converted_image_has_the_new_size(old in old_rl()) {
+            let bytes = NewRL::from_old(&old).to_bytes();
+
+            prop_assert_eq!(bytes.len(), NEW_RL_SIZE);
+        }
+    }
+
+    fn address(byte: u8) -> Id {
+        Id { data: [byte; 32] }
+    }
+
+    /// A selling state with a 10/20/50/20 split, drawing on Wednesday and
+    /// Saturday at 12:00, with two players and two winners.
+    fn sample_new() -> NewRL {
+        let mut new_rl = NewRL::builder()
+            .team_address(address(0xAA))
+            .owner_address(address(0xBB))
+            .team_fee_percent(10)
+            .distribution_fee_percent(20)
+            .winner_fee_percent(50)
+            .burn_percent(20)
+            .ticket_price(1_000_000)
+            .schedule(0b0000_1001)
+            .draw_hour(12)
+            .current_state(EState::Selling)
+            .build()
+            .unwrap();
+        new_rl.players[0] = address(1);
+        new_rl.players[1] = address(2);
+        new_rl.player_counter = 2;
+        new_rl.record_winner(WinnerInfoNew::new(address(3), 5_000, 1_000, 150, 0));
+        new_rl.record_winner(WinnerInfoNew::new(address(4), 7_000, 2_000, 151, 3));
+        new_rl
+    }
+
+    #[test]
+    fn full_fee_split_is_valid() {
+        assert_eq!(sample_new().validate_fees(), Ok(()));
+    }
+
+    #[test]
+    fn short_fee_split_is_rejected() {
+        let mut new_rl = sample_new();
+        new_rl.winner_fee_percent = 47;
+        let error = new_rl.validate_fees().unwrap_err();
+        assert_eq!(error.total(), 97);
+        assert_eq!(
+            NewRL::builder()
+                .team_fee_percent(10)
+                .distribution_fee_percent(20)
+                .winner_fee_percent(47)
+                .burn_percent(20)
+                .build()
+                .unwrap_err(),
+            BuildError::InvalidFees(error)
+        );
+    }
+
+    /// A state that recorded `count` draws, the n-th one in epoch n and tick n.
+    fn with_draws(count: usize) -> NewRL {
+        let mut new_rl = NewRL::default();
+        for n in 1..=count {
+            new_rl.record_winner(WinnerInfoNew::new(
+                address(n as u8 | 1),
+                n as u64,
+                n as u32,
+                n as u16,
+                0,
+            ));
+        }
+        new_rl
+    }
+
+    #[test]
+    fn chronological_order_before_the_wrap() {
+        let epochs: Vec<u16> = with_draws(5)
+            .winners_chronological()
+            .map(|w| w.epoch)
+            .collect();
+        assert_eq!(epochs, [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn chronological_order_after_the_wrap() {
+        let draws = RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY + 6;
+        let new_rl = with_draws(draws);
+        assert_eq!(new_rl.winners_counter, draws as u64);
+        assert!(new_rl.has_wrapped());
+
+        let epochs: Vec<u16> = new_rl.winners_chronological().map(|w| w.epoch).collect();
+        let expected: Vec<u16> = (7..=draws as u16).collect();
+        assert_eq!(epochs, expected);
+    }
+
+    #[test]
+    fn wednesday_and_saturday_at_noon_is_a_valid_schedule() {
+        let new_rl = sample_new();
+        assert_eq!(new_rl.schedule, 0b0000_1001);
+        assert_eq!(new_rl.draw_hour, 12);
+        assert_eq!(new_rl.validate_schedule(), Ok(()));
+    }
+
+    #[test]
+    fn out_of_range_schedule_is_rejected() {
+        let mut new_rl = sample_new();
+        new_rl.draw_hour = 25;
+        assert_eq!(
+            new_rl.validate_schedule(),
+            Err(ScheduleError::InvalidDrawHour(25))
+        );
+
+        let mut new_rl = sample_new();
+        new_rl.schedule = 0b1000_0001;
+        assert_eq!(
+            new_rl.validate_schedule(),
+            Err(ScheduleError::InvalidScheduleBits(0b1000_0001))
+        );
+    }
+
+    #[test]
+    fn builder_sets_the_given_fields_and_zeroes_the_rest() {
+        let new_rl = sample_new();
+        assert_eq!(new_rl.team_address, address(0xAA));
+        assert_eq!(new_rl.owner_address, address(0xBB));
+        assert_eq!(new_rl.ticket_price, 1_000_000);
+        assert_eq!(new_rl.current_state, EState::Selling);
+
+        let fresh = NewRL::builder().winner_fee_percent(100).build().unwrap();
+        let expected = NewRL {
+            winner_fee_percent: 100,
+            ..NewRL::default()
+        };
+        assert_eq!(fresh.to_bytes(), expected.to_bytes());
+    }
+
+    #[test]
+    fn builder_rejects_an_invalid_schedule() {
+        let result = NewRL::builder()
+            .winner_fee_percent(100)
+            .draw_hour(24)
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            BuildError::InvalidSchedule(ScheduleError::InvalidDrawHour(24))
+        );
+    }
+
+    #[test]
+    fn active_player_count_ignores_a_stale_counter() {
+        let mut new_rl = sample_new();
+        new_rl.player_counter = 5;
+        new_rl.players[9] = address(9);
+        new_rl.players[0] = Id::zero();
+        assert_eq!(new_rl.active_player_count(), 2);
+        assert_eq!(
+            new_rl.players_iter().copied().collect::<Vec<_>>(),
+            [address(2), address(9)]
+        );
+    }
+
+    #[test]
+    fn selling_with_zero_price_is_flagged() {
+        let mut new_rl = sample_new();
+        assert_eq!(new_rl.validate_state(), Ok(()));
+        new_rl.ticket_price = 0;
+        assert_eq!(
+            new_rl.validate_state(),
+            Err(StateError::SellingWithZeroPrice)
+        );
+    }
+
+    #[test]
+    fn locked_with_players_is_flagged() {
+        let mut new_rl = sample_new();
+        new_rl.current_state = EState::Locked;
+        assert_eq!(
+            new_rl.validate_state(),
+            Err(StateError::LockedWithPlayers(2))
+        );
+        new_rl.players = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+        assert_eq!(new_rl.validate_state(), Ok(()));
+    }
+
+    #[test]
+    fn semantic_eq_ignores_the_draw_guard() {
+        let a = sample_new();
+        let mut b = sample_new();
+        b.last_draw_day = 3;
+        b.last_draw_hour = 12;
+        b.last_draw_date_stamp = 20_250_104;
+        assert!(a.semantic_eq(&b));
+        assert_ne!(a.to_bytes(), b.to_bytes());
+    }
+
+    #[test]
+    fn semantic_eq_sees_persistent_fields() {
+        let a = sample_new();
+        let mut b = sample_new();
+        b.winners[1].revenue += 1;
+        assert!(!a.semantic_eq(&b));
+
+        let mut b = sample_new();
+        b.schedule = 0b0000_0001;
+        assert!(!a.semantic_eq(&b));
+    }
+
+    #[test]
+    fn identical_states_write_identical_bytes() {
+        assert_eq!(sample_new().to_bytes(), sample_new().to_bytes());
+    }
+
+    #[test]
+    fn padding_is_written_as_zero() {
+        let mut image = sample_new().to_bytes();
+        // The byte after day_of_week of winner 0 is padding
+        let padding = offset_of!(WinnerInfoNew, day_of_week) + 1;
+        image[padding] = 0xEE;
+        let decoded = NewRL::from_bytes(&image).unwrap();
+        assert_eq!(decoded.to_bytes(), sample_new().to_bytes());
+    }
+
+    #[test]
+    fn next_epoch_data_is_applied_and_cleared() {
+        let mut new_rl = sample_new();
+        new_rl.next_epoch_data = NextEpochData::new(2_000_000, 0b0010_0010);
+        new_rl.apply_next_epoch().unwrap();
+        assert_eq!(new_rl.ticket_price, 2_000_000);
+        assert_eq!(new_rl.schedule, 0b0010_0010);
+        assert_eq!(new_rl.next_epoch_data, NextEpochData::default());
+        assert_eq!(new_rl.active_player_count(), 2);
+    }
+
+    #[test]
+    fn total_revenue_of_near_max_winners_does_not_overflow() {
+        let mut new_rl = NewRL::default();
+        for n in 0..RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY {
+            new_rl.record_winner(WinnerInfoNew::new(address(1), u64::MAX - 1, 0, n as u16, 0));
+        }
+        assert_eq!(
+            new_rl.total_revenue(),
+            u128::from(u64::MAX - 1) * RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY as u128
+        );
+    }
+
+    #[test]
+    fn clone_and_rebuild_have_the_same_content_hash() {
+        let original = sample_new();
+        let hash = original.content_hash();
+        assert_eq!(original.clone().content_hash(), hash);
+        assert_eq!(sample_new().content_hash(), hash);
+
+        let mut changed = sample_new();
+        changed.draw_hour = 13;
+        assert_ne!(changed.content_hash(), hash);
+    }
+
+    #[test]
+    fn cleared_middle_winner_is_skipped_and_compacted_away() {
+        let mut new_rl = with_draws(3);
+        new_rl.clear_winner(1).unwrap();
+        assert!(new_rl.winners[1].winner_address.is_zero());
+        assert_eq!(new_rl.winners_counter, 3);
+        let epochs: Vec<u16> = new_rl.winners_chronological().map(|w| w.epoch).collect();
+        assert_eq!(epochs, [1, 3]);
+
+        new_rl.compact_winners();
+        assert_eq!(new_rl.winners_counter, 2);
+        assert_eq!(new_rl.winners[0].epoch, 1);
+        assert_eq!(new_rl.winners[1].epoch, 3);
+        assert!(new_rl.winners[2].winner_address.is_zero());
+        let epochs: Vec<u16> = new_rl.winners_chronological().map(|w| w.epoch).collect();
+        assert_eq!(epochs, [1, 3]);
+    }
+
+    #[test]
+    fn clearing_past_the_history_is_an_error() {
+        let mut new_rl = with_draws(1);
+        assert_eq!(
+            new_rl.clear_winner(RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY),
+            Err(IndexError {
+                index: RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY,
+                len: RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY
+            })
+        );
+    }
+
+    #[test]
+    fn old_winner_converts_with_an_unknown_weekday() {
+        let old = WinnerInfo {
+            winner_address: address(7),
+            revenue: 123_456,
+            epoch: 175,
+            tick: 9_876_543,
+        };
+        let expected = WinnerInfoNew::new(address(7), 123_456, 9_876_543, 175, 0);
+        assert_eq!(WinnerInfoNew::from(&old), expected);
+        assert_eq!(WinnerInfoNew::from(old), expected);
+    }
+
+    #[test]
+    fn winners_match_the_collected_vec_conversion() {
+        let mut old = OldRL::default();
+        for (i, winner) in old.winners.iter_mut().enumerate().step_by(3) {
+            *winner = WinnerInfo {
+                winner_address: address(i as u8 | 1),
+                revenue: i as u64 * 1_000 + 1,
+                epoch: i as u16,
+                tick: i as u32 * 7,
+            };
+        }
+        // The former implementation: collect into a Vec, then back into an array
+        let collected: [WinnerInfoNew; RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY] = old
+            .winners
+            .iter()
+            .map(|w| WinnerInfoNew {
+                winner_address: w.winner_address,
+                revenue: w.revenue,
+                tick: w.tick,
+                epoch: w.epoch,
+                day_of_week: 0,
+            })
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+        assert_eq!(NewRL::from_old(&old).winners, collected);
+    }
+
+    #[test]
+    fn epoch_range_spans_the_wrap_point() {
+        // Epochs 1025..=1030 were written over slots 0..=5
+        let new_rl = with_draws(RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY + 6);
+        let epochs: Vec<u16> = new_rl
+            .winners_in_epochs(1020, 1027)
+            .map(|w| w.epoch)
+            .collect();
+        assert_eq!(epochs, (1020..=1027).collect::<Vec<u16>>());
+    }
+
+    #[test]
+    fn epoch_range_bounds_are_inclusive() {
+        let new_rl = with_draws(10);
+        let epochs: Vec<u16> = new_rl.winners_in_epochs(3, 5).map(|w| w.epoch).collect();
+        assert_eq!(epochs, [3, 4, 5]);
+        assert_eq!(new_rl.winners_in_epochs(4, 4).count(), 1);
+        assert_eq!(new_rl.winners_in_epochs(5, 3).count(), 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_has_stored_and_derived_counts() {
+        let new_rl = NewRL {
+            player_counter: 5,
+            ..sample_new()
+        };
+        let json = serde_json::to_value(new_rl).unwrap();
+        assert_eq!(json["player_counter"], 5);
+        assert_eq!(json["active_player_count"], 2);
+        assert_eq!(json["winners_counter"], 2);
+        assert_eq!(json["winner_count"], 2);
+    }
+
+    #[test]
+    fn merge_keeps_shared_winners_once() {
+        let first = NewRL {
+            draw_hour: 12,
+            ..with_draws(4)
+        };
+        // Epochs 3 and 4 are in both histories
+        let mut second = with_draws(6);
+        second.clear_winner(0).unwrap();
+        second.clear_winner(1).unwrap();
+
+        let merged = first.merge_winners(&second);
+        let epochs: Vec<u16> = merged.winners_chronological().map(|w| w.epoch).collect();
+        assert_eq!(epochs, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(merged.winners_counter, 6);
+        assert_eq!(merged.draw_hour, 12);
+    }
+
+    #[test]
+    fn merge_keeps_the_most_recent_winners_on_overflow() {
+        let first = with_draws(RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY);
+        let second = with_draws(RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY + 6);
+
+        let merged = first.merge_winners(&second);
+        assert_eq!(
+            merged.winners_counter,
+            RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY as u64 + 6
+        );
+        let epochs: Vec<u16> = merged.winners_chronological().map(|w| w.epoch).collect();
+        assert_eq!(epochs, (7..=1030).collect::<Vec<u16>>());
+    }
+
+    #[test]
+    fn players_iter_skips_empty_slots() {
+        let mut new_rl = sample_new();
+        new_rl.players[9] = address(9);
+        let ids: Vec<Id> = new_rl.players_iter().copied().collect();
+        assert_eq!(ids, [address(1), address(2), address(9)]);
+    }
+
+    #[test]
+    fn owner_equal_to_team_is_flagged() {
+        assert_eq!(sample_new().validate_addresses(), Ok(()));
+        let same = NewRL {
+            owner_address: address(0xAA),
+            ..sample_new()
+        };
+        assert_eq!(same.validate_addresses(), Err(StateError::OwnerIsTeam));
+        // Two zero addresses are reported as zero, not as equal
+        assert_eq!(NewRL::default().validate_addresses(), Ok(()));
+    }
+
+    #[test]
+    fn zero_owner_while_selling_is_flagged() {
+        let unowned = NewRL {
+            owner_address: Id::zero(),
+            ..sample_new()
+        };
+        assert_eq!(unowned.zero_addresses_while_selling(), ["owner_address"]);
+        let locked = NewRL {
+            current_state: EState::Locked,
+            ..unowned
+        };
+        assert!(locked.zero_addresses_while_selling().is_empty());
+        assert!(sample_new().zero_addresses_while_selling().is_empty());
+    }
+
+    #[test]
+    fn winner_display_snapshot() {
+        let mut winner_address = Id::zero();
+        winner_address.data[0] = 4;
+        let winner = WinnerInfoNew::new(winner_address, 500, 12_345, 100, 2);
+        assert_eq!(
+            winner.to_string(),
+            "EAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAVWRF: \
+             500 units, epoch 100, tick 12345, day of week 2"
+        );
+    }
+
+    #[test]
+    fn next_epoch_data_display_snapshot() {
+        assert_eq!(
+            NextEpochData::new(1_000_000, 0b0000_0101).to_string(),
+            "price 1000000 units, schedule 0b00000101 (WED, FRI)"
+        );
+    }
+
+    #[test]
+    fn size_constant_matches_the_struct_and_its_image() {
+        assert_eq!(NEW_RL_SIZE, size_of::<NewRL>());
+        assert_eq!(sample_new().to_bytes().len(), NEW_RL_SIZE);
+    }
+
+    #[test]
+    fn absurd_winners_counter_is_flagged() {
+        assert_eq!(sample_new().validate_winners_counter(), Ok(()));
+
+        let absurd = NewRL {
+            winners_counter: u64::MAX,
+            ..sample_new()
+        };
+        let error = absurd.validate_winners_counter().unwrap_err();
+        assert_eq!(
+            error,
+            StateError::ImplausibleWinnersCounter {
+                counter: u64::MAX,
+                recorded: 2
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "winners_counter 18446744073709551615 claims a wrapped history, \
+             but only 2 of 1024 winner slots are filled"
+        );
+
+        let short = NewRL {
+            winners_counter: 1,
+            ..sample_new()
+        };
+        assert!(short.validate_winners_counter().is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn cursor_round_trips_through_to_writer_and_from_reader() {
+        let new_rl = sample_new();
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        assert_eq!(new_rl.to_writer(&mut cursor).unwrap(), NEW_RL_SIZE);
+        assert_eq!(cursor.get_ref(), &new_rl.to_bytes());
+
+        cursor.set_position(0);
+        assert_eq!(NewRL::from_reader(&mut cursor).unwrap(), new_rl);
+    }
+
+    #[test]
+    fn invalid_next_schedule_is_rejected_before_applying() {
+        let mut new_rl = sample_new();
+        // Bit 7 names no weekday
+        new_rl.next_epoch_data = NextEpochData::new(2_000_000, 0b1000_0001);
+        let before = new_rl;
+        assert_eq!(
+            new_rl.apply_next_epoch(),
+            Err(ScheduleError::InvalidNextScheduleBits(0b1000_0001))
+        );
+        assert_eq!(new_rl, before);
+        assert_eq!(
+            new_rl.validate_schedule(),
+            Err(ScheduleError::InvalidNextScheduleBits(0b1000_0001))
+        );
+    }
+
+    #[test]
+    fn rollover_clears_players_and_appends_the_winner() {
+        let mut new_rl = sample_new();
+        new_rl.next_epoch_data = NextEpochData::new(2_000_000, 0b0000_0010);
+        let winner = WinnerInfoNew::new(address(5), 9_000, 3_000, 152, 1);
+        new_rl.simulate_rollover(Some(winner)).unwrap();
+
+        assert_eq!(new_rl.active_player_count(), 0);
+        assert_eq!(new_rl.player_counter, 0);
+        assert_eq!(new_rl.ticket_price, 2_000_000);
+        assert_eq!(new_rl.schedule, 0b0000_0010);
+        assert_eq!(new_rl.winners_counter, 3);
+        assert_eq!(new_rl.winners_chronological().last(), Some(&winner));
+        assert_eq!(new_rl.winners[2], winner);
+    }
+
+    #[test]
+    fn rollover_without_a_winner_keeps_the_history() {
+        let mut new_rl = sample_new();
+        new_rl.next_epoch_data = NextEpochData::new(1_000_000, 0b0000_1001);
+        new_rl.simulate_rollover(None).unwrap();
+        assert_eq!(new_rl.active_player_count(), 0);
+        assert_eq!(new_rl.winners_counter, 2);
+        assert_eq!(new_rl.winners_chronological().count(), 2);
+    }
+
+    #[test]
+    fn in_order_history_passes() {
+        assert!(with_draws(5).winners_out_of_order().is_empty());
+        assert!(with_draws(RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY + 6)
+            .winners_out_of_order()
+            .is_empty());
+    }
+
+    #[test]
+    fn scrambled_history_is_flagged_then_fixed_by_the_sort() {
+        let mut new_rl = with_draws(5);
+        new_rl.winners.swap(1, 3);
+        // Ring order now reads epochs 1, 4, 3, 2, 5
+        assert_eq!(new_rl.winners_out_of_order(), [2, 3]);
+
+        new_rl.sort_winners();
+        assert!(new_rl.winners_out_of_order().is_empty());
+        assert_eq!(new_rl.winners_counter, 5);
+        let epochs: Vec<u16> = new_rl.winners_chronological().map(|w| w.epoch).collect();
+        assert_eq!(epochs, [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn constructors_set_every_field() {
+        let winner = WinnerInfoNew::new(address(1), 500, 12_345, 100, 2);
+        assert_eq!(
+            winner,
+            WinnerInfoNew {
+                winner_address: address(1),
+                revenue: 500,
+                tick: 12_345,
+                epoch: 100,
+                day_of_week: 2,
+            }
+        );
+        assert_eq!(
+            NextEpochData::new(1_000, 0b0000_0101),
+            NextEpochData {
+                new_price: 1_000,
+                schedule: 0b0000_0101,
+            }
+        );
+        let built = NewRL::builder()
+            .winner_fee_percent(100)
+            .next_epoch_data(NextEpochData::new(1_000, 0b0000_0101))
+            .build()
+            .unwrap();
+        assert_eq!(
+            built,
+            NewRL {
+                winner_fee_percent: 100,
+                next_epoch_data: NextEpochData::new(1_000, 0b0000_0101),
+                ..NewRL::default()
+            }
+        );
+    }
 }
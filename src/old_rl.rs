@@ -8,11 +8,21 @@
  * - Distributes fees (team, distribution, burn, winner).
  * - Records winners' history in a ring-like buffer.
  */
-use crate::common::{EState, Id, RL_MAX_NUMBER_OF_PLAYERS, RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY};
-use std::fmt::{self, Display, Formatter};
+use crate::common::{
+    centered, DisplayConfig, EState, FieldReader, FieldWriter, Id, RL_MAX_NUMBER_OF_PLAYERS,
+    RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY,
+};
+use crate::error::ConvertError;
+use crate::new_rl::NewRL;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use core::mem::{offset_of, size_of};
 
 /// Winner snapshot for an epoch.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(C)]
 pub struct WinnerInfo {
     pub winner_address: Id,
@@ -21,6 +31,7 @@ pub struct WinnerInfo {
     pub tick: u32,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for WinnerInfo {
     fn default() -> Self {
         Self {
@@ -33,10 +44,15 @@ impl Default for WinnerInfo {
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(C)]
 pub struct PlayerHashSet {
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::common::serialize_active_ids")
+    )]
     pub players: [Id; RL_MAX_NUMBER_OF_PLAYERS],
-    pub occupation_flags: [u64; (RL_MAX_NUMBER_OF_PLAYERS * 2 + 63) / 64],
+    pub occupation_flags: [u64; (RL_MAX_NUMBER_OF_PLAYERS * 2).div_ceil(64)],
     pub population: u64,
     pub mark_removal_counter: u64,
 }
@@ -45,13 +61,132 @@ impl Default for PlayerHashSet {
     fn default() -> Self {
         Self {
             players: [Id::default(); RL_MAX_NUMBER_OF_PLAYERS],
-            occupation_flags: [0u64; (RL_MAX_NUMBER_OF_PLAYERS * 2 + 63) / 64],
+            occupation_flags: [0u64; (RL_MAX_NUMBER_OF_PLAYERS * 2).div_ceil(64)],
             population: 0,
             mark_removal_counter: 0,
         }
     }
 }
 
+/// Inconsistency between a `PlayerHashSet`'s occupation flags and its contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashSetError {
+    /// Stored `population` counter.
+    pub population: u64,
+    /// Number of slots whose occupation flag is set.
+    pub occupied: u64,
+    /// Indices of slots flagged as occupied that hold the zero address.
+    pub zero_occupied_slots: Vec<usize>,
+}
+
+impl Display for HashSetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut problems = Vec::new();
+        if self.population != self.occupied {
+            problems.push(format!(
+                "population is {} but {} slots are flagged as occupied",
+                self.population, self.occupied
+            ));
+        }
+        if !self.zero_occupied_slots.is_empty() {
+            problems.push(format!(
+                "occupied slots {:?} hold the zero address",
+                self.zero_occupied_slots
+            ));
+        }
+        write!(
+            f,
+            "player hash set is inconsistent: {}",
+            problems.join("; ")
+        )
+    }
+}
+
+impl core::error::Error for HashSetError {}
+
+impl PlayerHashSet {
+    /// Whether the occupation flag (low bit of the slot's 2-bit entry) is set.
+    pub fn is_occupied(&self, index: usize) -> bool {
+        self.occupation_flags[index >> 5] & (1u64 << ((index & 31) << 1)) != 0
+    }
+
+    /// Occupied slots with their index, in slot order.
+    ///
+    /// Membership comes from the occupation flags alone: an occupied slot is
+    /// yielded even if it holds a zero id, and a leftover id in a freed slot
+    /// is skipped.
+    pub fn occupied_slots(&self) -> impl Iterator<Item = (usize, &Id)> {
+        self.players
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.is_occupied(*i))
+    }
+
+    /// Ids of the occupied slots, in slot order (see `occupied_slots`).
+    pub fn occupied_players(&self) -> impl Iterator<Item = &Id> {
+        self.occupied_slots().map(|(_, id)| id)
+    }
+
+    /// Checks that `population` matches the occupation flags and that every
+    /// occupied slot holds a non-zero address.
+    pub fn verify(&self) -> Result<(), HashSetError> {
+        let mut occupied = 0;
+        let mut zero_occupied_slots = Vec::new();
+        for (i, player) in self.players.iter().enumerate() {
+            if self.is_occupied(i) {
+                occupied += 1;
+                if player.is_zero() {
+                    zero_occupied_slots.push(i);
+                }
+            }
+        }
+
+        if occupied == self.population && zero_occupied_slots.is_empty() {
+            Ok(())
+        } else {
+            Err(HashSetError {
+                population: self.population,
+                occupied,
+                zero_occupied_slots,
+            })
+        }
+    }
+
+    /// Non-zero ids held by more than one occupied slot, each listed once in
+    /// order of first occurrence.
+    ///
+    /// A healthy set never stores an id twice; duplicates would give that
+    /// player extra weight in the draw. Zero ids are reported by `verify`.
+    pub fn find_duplicates(&self) -> Vec<Id> {
+        let mut seen = BTreeSet::new();
+        let mut duplicates = Vec::new();
+        for id in self.occupied_players().filter(|id| !id.is_zero()) {
+            if !seen.insert(id.data) && !duplicates.contains(id) {
+                duplicates.push(*id);
+            }
+        }
+        duplicates
+    }
+
+    /// Rebuilds a hash set from a flat slot array, keeping slot positions.
+    ///
+    /// Every non-zero slot is marked as occupied (the low bit of its 2-bit
+    /// occupation flag) and counted in `population`; no slot is marked for removal.
+    pub fn from_players(players: &[Id; RL_MAX_NUMBER_OF_PLAYERS]) -> Self {
+        let mut set = Self {
+            players: *players,
+            ..Self::default()
+        };
+        for (i, player) in players.iter().enumerate() {
+            if !player.is_zero() {
+                set.occupation_flags[i >> 5] |= 1u64 << ((i & 31) << 1);
+                set.population += 1;
+            }
+        }
+        set
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct OldRL {
@@ -68,6 +203,75 @@ pub struct OldRL {
     pub current_state: EState,
 }
 
+/// Size in bytes of an `OldRL` state file.
+pub const OLD_RL_SIZE: usize = size_of::<OldRL>();
+
+// Pin the on-disk layout. A size change fails the build with
+// "expected an array with a size of <expected>, found one with a size of <actual>".
+const _: [(); 48] = [(); size_of::<WinnerInfo>()];
+const _: [(); 33040] = [(); size_of::<PlayerHashSet>()];
+const _: [(); 82288] = [(); OLD_RL_SIZE];
+const _: () = {
+    assert!(
+        offset_of!(OldRL, team_address) == 0,
+        "OldRL.team_address must be at offset 0"
+    );
+    assert!(
+        offset_of!(OldRL, ticket_price) == 72,
+        "OldRL.ticket_price must be at offset 72"
+    );
+    assert!(
+        offset_of!(OldRL, players) == 80,
+        "OldRL.players must be at offset 80"
+    );
+    assert!(
+        offset_of!(OldRL, winners) == 33120,
+        "OldRL.winners must be at offset 33120"
+    );
+    assert!(
+        offset_of!(OldRL, winners_info_next_empty_index) == 82272,
+        "OldRL.winners_info_next_empty_index must be at offset 82272"
+    );
+    assert!(
+        offset_of!(OldRL, current_state) == 82280,
+        "OldRL.current_state must be at offset 82280"
+    );
+};
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for OldRL {
+    /// Stored fields in declaration order, with empty winner slots omitted,
+    /// followed by the derived `active_player_count` and `winner_count`.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let winners: Vec<&WinnerInfo> = self
+            .winners
+            .iter()
+            .filter(|w| !w.winner_address.is_zero())
+            .collect();
+
+        let mut state = serializer.serialize_struct("OldRL", 13)?;
+        state.serialize_field("team_address", &self.team_address)?;
+        state.serialize_field("owner_address", &self.owner_address)?;
+        state.serialize_field("team_fee_percent", &self.team_fee_percent)?;
+        state.serialize_field("distribution_fee_percent", &self.distribution_fee_percent)?;
+        state.serialize_field("winner_fee_percent", &self.winner_fee_percent)?;
+        state.serialize_field("burn_percent", &self.burn_percent)?;
+        state.serialize_field("ticket_price", &self.ticket_price)?;
+        state.serialize_field("players", &self.players)?;
+        state.serialize_field("winners", &winners)?;
+        state.serialize_field(
+            "winners_info_next_empty_index",
+            &self.winners_info_next_empty_index,
+        )?;
+        state.serialize_field("current_state", &self.current_state)?;
+        state.serialize_field("active_player_count", &self.active_player_count())?;
+        state.serialize_field("winner_count", &winners.len())?;
+        state.end()
+    }
+}
+
 impl Default for OldRL {
     fn default() -> Self {
         Self {
@@ -86,70 +290,598 @@ impl Default for OldRL {
     }
 }
 
+impl OldRL {
+    /// Sum of `revenue` over all recorded winners, widened like
+    /// `NewRL::total_revenue` so it cannot overflow.
+    pub fn total_revenue(&self) -> u128 {
+        self.winners_chronological()
+            .map(|w| u128::from(w.revenue))
+            .sum()
+    }
+
+    /// Active players in slot order: the ids of occupied hash set slots,
+    /// skipping stale ids in freed slots and the zero address an
+    /// inconsistent set may hold in an occupied one.
+    pub fn players_iter(&self) -> impl Iterator<Item = &Id> {
+        self.players.occupied_players().filter(|id| !id.is_zero())
+    }
+
+    /// Number of active players, as yielded by `players_iter`.
+    pub fn active_player_count(&self) -> usize {
+        self.players_iter().count()
+    }
+
+    /// Recorded winners from oldest to newest, skipping empty slots.
+    ///
+    /// Same ring-buffer walk as `NewRL::winners_chronological`, starting at
+    /// `winners_info_next_empty_index`.
+    pub fn winners_chronological(&self) -> impl Iterator<Item = &WinnerInfo> {
+        let start = (self.winners_info_next_empty_index
+            % RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY as u64) as usize;
+        self.winners[start..]
+            .iter()
+            .chain(self.winners[..start].iter())
+            .filter(|w| !w.winner_address.is_zero())
+    }
+
+    /// Recorded winners with `start <= epoch <= end`, oldest first (see
+    /// `NewRL::winners_in_epochs`).
+    pub fn winners_in_epochs(&self, start: u16, end: u16) -> impl Iterator<Item = &WinnerInfo> {
+        self.winners_chronological()
+            .filter(move |w| (start..=end).contains(&w.epoch))
+    }
+
+    /// Decodes an `OldRL` from its on-disk byte image.
+    ///
+    /// Every field is read explicitly at its `repr(C)` offset, so padding is
+    /// ignored and an out-of-range `current_state` byte is reported as an
+    /// error instead of producing an invalid `EState`.
+    pub fn from_bytes(buf: &[u8]) -> Result<OldRL, ConvertError> {
+        let reader = FieldReader::new(buf, OLD_RL_SIZE)?;
+
+        let players_base = offset_of!(OldRL, players);
+        let players = PlayerHashSet {
+            players: core::array::from_fn(|i| {
+                reader.id(players_base + offset_of!(PlayerHashSet, players) + i * size_of::<Id>())
+            }),
+            occupation_flags: core::array::from_fn(|i| {
+                reader.u64(
+                    players_base
+                        + offset_of!(PlayerHashSet, occupation_flags)
+                        + i * size_of::<u64>(),
+                )
+            }),
+            population: reader.u64(players_base + offset_of!(PlayerHashSet, population)),
+            mark_removal_counter: reader
+                .u64(players_base + offset_of!(PlayerHashSet, mark_removal_counter)),
+        };
+
+        let winners = core::array::from_fn(|i| {
+            let base = offset_of!(OldRL, winners) + i * size_of::<WinnerInfo>();
+            WinnerInfo {
+                winner_address: reader.id(base + offset_of!(WinnerInfo, winner_address)),
+                revenue: reader.u64(base + offset_of!(WinnerInfo, revenue)),
+                epoch: reader.u16(base + offset_of!(WinnerInfo, epoch)),
+                tick: reader.u32(base + offset_of!(WinnerInfo, tick)),
+            }
+        });
+
+        Ok(OldRL {
+            team_address: reader.id(offset_of!(OldRL, team_address)),
+            owner_address: reader.id(offset_of!(OldRL, owner_address)),
+            team_fee_percent: reader.u8(offset_of!(OldRL, team_fee_percent)),
+            distribution_fee_percent: reader.u8(offset_of!(OldRL, distribution_fee_percent)),
+            winner_fee_percent: reader.u8(offset_of!(OldRL, winner_fee_percent)),
+            burn_percent: reader.u8(offset_of!(OldRL, burn_percent)),
+            ticket_price: reader.u64(offset_of!(OldRL, ticket_price)),
+            players,
+            winners,
+            winners_info_next_empty_index: reader
+                .u64(offset_of!(OldRL, winners_info_next_empty_index)),
+            current_state: reader.state(offset_of!(OldRL, current_state))?,
+        })
+    }
+
+    /// Reads and decodes an `OldRL` image from any `std::io` reader.
+    ///
+    /// At most one byte past `OLD_RL_SIZE` is consumed, so a longer stream
+    /// fails with `SizeMismatch` (reporting that one extra byte) instead of
+    /// being read to its end.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: std::io::Read>(reader: &mut R) -> Result<OldRL, ConvertError> {
+        OldRL::from_bytes(&crate::common::read_image(reader, OLD_RL_SIZE)?)
+    }
+
+    /// Encodes the state into its on-disk byte image.
+    ///
+    /// Inverse of `from_bytes`: fields are written at their `repr(C)`
+    /// offsets with little-endian integers and padding bytes are zero.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = FieldWriter::new(OLD_RL_SIZE);
+
+        writer.id(offset_of!(OldRL, team_address), &self.team_address);
+        writer.id(offset_of!(OldRL, owner_address), &self.owner_address);
+        writer.u8(offset_of!(OldRL, team_fee_percent), self.team_fee_percent);
+        writer.u8(
+            offset_of!(OldRL, distribution_fee_percent),
+            self.distribution_fee_percent,
+        );
+        writer.u8(
+            offset_of!(OldRL, winner_fee_percent),
+            self.winner_fee_percent,
+        );
+        writer.u8(offset_of!(OldRL, burn_percent), self.burn_percent);
+        writer.u64(offset_of!(OldRL, ticket_price), self.ticket_price);
+
+        let players_base = offset_of!(OldRL, players);
+        for (i, player) in self.players.players.iter().enumerate() {
+            writer.id(
+                players_base + offset_of!(PlayerHashSet, players) + i * size_of::<Id>(),
+                player,
+            );
+        }
+        for (i, flags) in self.players.occupation_flags.iter().enumerate() {
+            writer.u64(
+                players_base + offset_of!(PlayerHashSet, occupation_flags) + i * size_of::<u64>(),
+                *flags,
+            );
+        }
+        writer.u64(
+            players_base + offset_of!(PlayerHashSet, population),
+            self.players.population,
+        );
+        writer.u64(
+            players_base + offset_of!(PlayerHashSet, mark_removal_counter),
+            self.players.mark_removal_counter,
+        );
+
+        for (i, w) in self.winners.iter().enumerate() {
+            let base = offset_of!(OldRL, winners) + i * size_of::<WinnerInfo>();
+            writer.id(
+                base + offset_of!(WinnerInfo, winner_address),
+                &w.winner_address,
+            );
+            writer.u64(base + offset_of!(WinnerInfo, revenue), w.revenue);
+            writer.u16(base + offset_of!(WinnerInfo, epoch), w.epoch);
+            writer.u32(base + offset_of!(WinnerInfo, tick), w.tick);
+        }
+
+        writer.u64(
+            offset_of!(OldRL, winners_info_next_empty_index),
+            self.winners_info_next_empty_index,
+        );
+        writer.u8(offset_of!(OldRL, current_state), self.current_state as u8);
+
+        writer.into_bytes()
+    }
+}
+
+/// Draw-guard fields appended after the `OldRL` image by an intermediate
+/// contract build.
+///
+/// Such "extended old" files are `OLD_RL_SIZE + size_of::<DrawGuard>()`
+/// bytes long. The fields have the same meaning, and the same relative
+/// layout, as the `last_draw_*` fields of `NewRL`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(C)]
+pub struct DrawGuard {
+    pub last_draw_day: u8,
+    pub last_draw_hour: u8,
+    pub last_draw_date_stamp: u32,
+}
+
+const _: [(); 8] = [(); size_of::<DrawGuard>()];
+
+impl DrawGuard {
+    /// Size in bytes of an extended old image: the `OldRL` image followed by
+    /// the draw-guard trailer.
+    pub const EXTENDED_OLD_SIZE: usize = OLD_RL_SIZE + size_of::<DrawGuard>();
+
+    /// Decodes the trailer from its byte image.
+    pub fn from_bytes(buf: &[u8]) -> Result<DrawGuard, ConvertError> {
+        let reader = FieldReader::new(buf, size_of::<DrawGuard>())?;
+        Ok(DrawGuard {
+            last_draw_day: reader.u8(offset_of!(DrawGuard, last_draw_day)),
+            last_draw_hour: reader.u8(offset_of!(DrawGuard, last_draw_hour)),
+            last_draw_date_stamp: reader.u32(offset_of!(DrawGuard, last_draw_date_stamp)),
+        })
+    }
+
+    /// Encodes the trailer into its byte image, padding bytes zero.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = FieldWriter::new(size_of::<DrawGuard>());
+        writer.u8(offset_of!(DrawGuard, last_draw_day), self.last_draw_day);
+        writer.u8(offset_of!(DrawGuard, last_draw_hour), self.last_draw_hour);
+        writer.u32(
+            offset_of!(DrawGuard, last_draw_date_stamp),
+            self.last_draw_date_stamp,
+        );
+        writer.into_bytes()
+    }
+}
+
+impl OldRL {
+    /// Decodes an extended old image, an `OldRL` followed by a `DrawGuard`
+    /// trailer (see `DrawGuard::EXTENDED_OLD_SIZE`).
+    pub fn from_extended_bytes(buf: &[u8]) -> Result<(OldRL, DrawGuard), ConvertError> {
+        if buf.len() != DrawGuard::EXTENDED_OLD_SIZE {
+            return Err(ConvertError::SizeMismatch {
+                expected: DrawGuard::EXTENDED_OLD_SIZE,
+                actual: buf.len(),
+            });
+        }
+        let (old, trailer) = buf.split_at(OLD_RL_SIZE);
+        Ok((OldRL::from_bytes(old)?, DrawGuard::from_bytes(trailer)?))
+    }
+}
+
+impl From<&NewRL> for OldRL {
+    fn from(new: &NewRL) -> Self {
+        // next_epoch_data, player_counter, draw guard and schedule fields
+        // have no counterpart in the old layout and are dropped.
+        Self {
+            team_address: new.team_address,
+            owner_address: new.owner_address,
+            team_fee_percent: new.team_fee_percent,
+            distribution_fee_percent: new.distribution_fee_percent,
+            winner_fee_percent: new.winner_fee_percent,
+            burn_percent: new.burn_percent,
+            ticket_price: new.ticket_price,
+            players: PlayerHashSet::from_players(&new.players),
+            winners: core::array::from_fn(|i| {
+                let w = &new.winners[i];
+                WinnerInfo {
+                    winner_address: w.winner_address,
+                    revenue: w.revenue,
+                    epoch: w.epoch,
+                    tick: w.tick,
+                }
+            }),
+            winners_info_next_empty_index: new.winners_counter,
+            current_state: new.current_state,
+        }
+    }
+}
+
 impl Display for OldRL {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        writeln!(
+        self.fmt_with_config(f, &DisplayConfig::default())
+    }
+}
+
+impl OldRL {
+    /// Writes the `Display` dump with `═` separators of `width` characters
+    /// and the title centered between them.
+    pub fn fmt_with_width(&self, f: &mut Formatter<'_>, width: usize) -> fmt::Result {
+        self.fmt_with_config(
             f,
-            "═══════════════════════════════════════════════════════════"
-        )?;
-        writeln!(f, "           OldRL STRUCTURE CONTENTS")?;
+            &DisplayConfig {
+                width,
+                ..DisplayConfig::default()
+            },
+        )
+    }
+
+    /// Writes the `Display` dump rendered as described by `config`.
+    pub fn fmt_with_config(&self, f: &mut Formatter<'_>, config: &DisplayConfig) -> fmt::Result {
+        let width = config.width;
+        let separator = "═".repeat(width);
+        let fee_total = u16::from(self.team_fee_percent)
+            + u16::from(self.distribution_fee_percent)
+            + u16::from(self.winner_fee_percent)
+            + u16::from(self.burn_percent);
+        let bad_fees = fee_total != 100;
+        // Same checks as NewRL::validate_addresses and zero_addresses_while_selling
+        let shared = self.owner_address == self.team_address && !self.owner_address.is_zero();
+        let selling = self.current_state == EState::Selling;
+        let team_alert = shared || (selling && self.team_address.is_zero());
+        let owner_alert = shared || (selling && self.owner_address.is_zero());
+        writeln!(f, "{}", separator)?;
         writeln!(
             f,
-            "═══════════════════════════════════════════════════════════\n"
+            "{}",
+            config.header(centered("OldRL STRUCTURE CONTENTS", width))
         )?;
+        writeln!(f, "{}\n", separator)?;
 
         // Addresses
-        writeln!(f, "📍 ADDRESSES:")?;
-        writeln!(f, "  Team Address:  {}", self.team_address)?;
-        writeln!(f, "  Owner Address: {}", self.owner_address)?;
+        writeln!(f, "{}", config.header("📍 ADDRESSES:"))?;
+        writeln!(
+            f,
+            "  Team Address:  {}",
+            config.alert(team_alert, config.id("team", &self.team_address))
+        )?;
+        writeln!(
+            f,
+            "  Owner Address: {}",
+            config.alert(owner_alert, config.id("owner", &self.owner_address))
+        )?;
 
         // Fees
-        writeln!(f, "\n💰 FEES:")?;
-        writeln!(f, "  Team:          {}%", self.team_fee_percent)?;
-        writeln!(f, "  Distribution:  {}%", self.distribution_fee_percent)?;
-        writeln!(f, "  Winner:        {}%", self.winner_fee_percent)?;
-        writeln!(f, "  Burn:          {}%", self.burn_percent)?;
+        writeln!(f, "\n{}", config.header("💰 FEES:"))?;
+        writeln!(
+            f,
+            "  Team:          {}%",
+            config.alert(bad_fees, self.team_fee_percent)
+        )?;
+        writeln!(
+            f,
+            "  Distribution:  {}%",
+            config.alert(bad_fees, self.distribution_fee_percent)
+        )?;
+        writeln!(
+            f,
+            "  Winner:        {}%",
+            config.alert(bad_fees, self.winner_fee_percent)
+        )?;
+        writeln!(
+            f,
+            "  Burn:          {}%",
+            config.alert(bad_fees, self.burn_percent)
+        )?;
 
         // Ticket price
-        writeln!(f, "\n🎫 TICKETS:")?;
+        writeln!(f, "\n{}", config.header("🎫 TICKETS:"))?;
         writeln!(f, "  Ticket price:  {} units", self.ticket_price)?;
 
         // Players
-        writeln!(f, "\n👥 PLAYERS:")?;
-        let active_players = self.players.players.iter().filter(|p| !p.is_zero()).count();
-        writeln!(f, "  Active players: {}", active_players)?;
+        writeln!(f, "\n{}", config.header("👥 PLAYERS:"))?;
+        writeln!(f, "  Active players: {}", self.active_player_count())?;
 
         writeln!(f, "  Players list:")?;
-        for (i, player) in self.players.players.iter().enumerate() {
-            if !player.is_zero() {
-                writeln!(f, "    {}. {}", i + 1, player)?;
-            }
+        let players = self
+            .players
+            .occupied_slots()
+            .filter(|(_, player)| !player.is_zero());
+        for (i, player) in players.take(config.shown()) {
+            writeln!(f, "    {}. {}", i + 1, config.id("player", player))?;
         }
+        config.write_remainder(f, self.active_player_count())?;
 
         // Winners
-        writeln!(f, "\n🏆 WINNERS HISTORY:")?;
+        writeln!(f, "\n{}", config.header("🏆 WINNERS HISTORY:"))?;
         writeln!(
             f,
             "  Next index:     {}",
             self.winners_info_next_empty_index
         )?;
 
-        writeln!(f, "  Winners list:")?;
-        for (i, winner) in self.winners.iter().enumerate() {
-            if !winner.winner_address.is_zero() {
-                writeln!(f, "    {}. Address: {}", i + 1, winner.winner_address)?;
-                writeln!(f, "       Prize:   {} units", winner.revenue)?;
-                writeln!(f, "       Epoch: {}, Tick: {}", winner.epoch, winner.tick)?;
-            }
+        writeln!(f, "  Winners list (oldest first):")?;
+        for (i, winner) in self
+            .winners_chronological()
+            .enumerate()
+            .take(config.shown())
+        {
+            writeln!(
+                f,
+                "    {}. Address: {}",
+                i + 1,
+                config.id("winner", &winner.winner_address)
+            )?;
+            writeln!(f, "       Prize:   {} units", winner.revenue)?;
+            writeln!(f, "       Epoch: {}, Tick: {}", winner.epoch, winner.tick)?;
         }
+        config.write_remainder(f, self.winners_chronological().count())?;
 
         // State
-        writeln!(f, "\n⚙️  STATE:")?;
-        writeln!(f, "  Current state: {:?}", self.current_state)?;
+        writeln!(f, "\n{}", config.header("⚙️  STATE:"))?;
+        writeln!(f, "  Current state: {}", self.current_state.as_str())?;
 
-        writeln!(
-            f,
-            "\n═══════════════════════════════════════════════════════════"
-        )
+        writeln!(f, "\n{}", separator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn id(byte: u8) -> Id {
+        Id { data: [byte; 32] }
+    }
+
+    /// A selling state with a 10/20/50/20 split, three players and two winners.
+    fn sample_old() -> OldRL {
+        let mut players = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+        players[0] = id(1);
+        players[1] = id(2);
+        players[7] = id(3);
+        let mut winners = [WinnerInfo::default(); RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY];
+        winners[0] = WinnerInfo {
+            winner_address: id(2),
+            revenue: 5_000,
+            epoch: 150,
+            tick: 1_000,
+        };
+        winners[1] = WinnerInfo {
+            winner_address: id(3),
+            revenue: 7_000,
+            epoch: 151,
+            tick: 2_000,
+        };
+        OldRL {
+            team_address: id(0xAA),
+            owner_address: id(0xBB),
+            team_fee_percent: 10,
+            distribution_fee_percent: 20,
+            winner_fee_percent: 50,
+            burn_percent: 20,
+            ticket_price: 1_000_000,
+            players: PlayerHashSet::from_players(&players),
+            winners,
+            winners_info_next_empty_index: 2,
+            current_state: EState::Selling,
+        }
+    }
+
+    #[test]
+    fn decodes_every_field() {
+        let old = OldRL::from_bytes(&sample_old().to_bytes()).unwrap();
+        assert_eq!(old.team_address, id(0xAA));
+        assert_eq!(old.owner_address, id(0xBB));
+        assert_eq!(
+            [
+                old.team_fee_percent,
+                old.distribution_fee_percent,
+                old.winner_fee_percent,
+                old.burn_percent
+            ],
+            [10, 20, 50, 20]
+        );
+        assert_eq!(old.ticket_price, 1_000_000);
+        assert_eq!(old.players.population, 3);
+        assert_eq!(old.players.players[7], id(3));
+        assert_eq!(old.winners[1].revenue, 7_000);
+        assert_eq!(old.winners_info_next_empty_index, 2);
+        assert_eq!(old.current_state, EState::Selling);
+    }
+
+    #[test]
+    fn rejects_an_invalid_state_byte() {
+        let mut bytes = sample_old().to_bytes();
+        bytes[offset_of!(OldRL, current_state)] = 7;
+        assert!(matches!(
+            OldRL::from_bytes(&bytes),
+            Err(ConvertError::InvalidState(7))
+        ));
+    }
+
+    #[test]
+    fn rejects_a_short_buffer() {
+        let bytes = sample_old().to_bytes();
+        assert!(matches!(
+            OldRL::from_bytes(&bytes[..OLD_RL_SIZE - 1]),
+            Err(ConvertError::SizeMismatch {
+                expected: OLD_RL_SIZE,
+                actual
+            }) if actual == OLD_RL_SIZE - 1
+        ));
+        assert!(OldRL::from_bytes(&[]).is_err());
+    }
+
+    #[test]
+    fn old_state_survives_a_round_trip_through_new() {
+        let old = sample_old();
+        let back = OldRL::from(&NewRL::from(&old));
+        assert_eq!(back.to_bytes(), old.to_bytes());
+    }
+
+    #[test]
+    fn new_only_fields_are_dropped_on_rollback() {
+        let mut new_rl = NewRL::from(&sample_old());
+        new_rl.schedule = 0b0101_0101;
+        new_rl.draw_hour = 12;
+        new_rl.player_counter = 3;
+        new_rl.last_draw_date_stamp = 99;
+        let back = OldRL::from(&new_rl);
+        assert_eq!(back.to_bytes(), sample_old().to_bytes());
+    }
+
+    #[test]
+    fn consistent_hash_set_verifies() {
+        assert_eq!(sample_old().players.verify(), Ok(()));
+    }
+
+    #[test]
+    fn inconsistent_occupation_flags_are_reported() {
+        let mut players = sample_old().players;
+        // Slot 7 loses its flag, slot 9 is flagged but empty
+        players.occupation_flags[0] &= !(1 << (7 << 1));
+        players.occupation_flags[0] |= 1 << (9 << 1);
+        players.population = 5;
+        assert_eq!(
+            players.verify(),
+            Err(HashSetError {
+                population: 5,
+                occupied: 3,
+                zero_occupied_slots: alloc::vec![9],
+            })
+        );
+    }
+
+    #[test]
+    fn iteration_follows_the_occupation_flags() {
+        let mut players = sample_old().players;
+        // Occupied but zero
+        players.occupation_flags[0] |= 1 << (4 << 1);
+        // Unoccupied but non-zero, a stale id
+        players.players[5] = id(0x55);
+
+        let slots: Vec<usize> = players.occupied_slots().map(|(i, _)| i).collect();
+        assert_eq!(slots, [0, 1, 4, 7]);
+        let ids: Vec<Id> = players.occupied_players().copied().collect();
+        assert_eq!(ids, [id(1), id(2), Id::zero(), id(3)]);
+    }
+
+    #[test]
+    fn total_revenue_of_near_max_winners_does_not_overflow() {
+        let mut old = sample_old();
+        old.winners[0].revenue = u64::MAX;
+        old.winners[1].revenue = u64::MAX - 1;
+        assert_eq!(old.total_revenue(), 2 * u128::from(u64::MAX) - 1);
+    }
+
+    #[test]
+    fn duplicated_player_is_found_once() {
+        let mut old = sample_old();
+        assert!(old.players.find_duplicates().is_empty());
+
+        let mut players = old.players.players;
+        players[9] = id(2);
+        players[10] = id(2);
+        old.players = PlayerHashSet::from_players(&players);
+        assert_eq!(old.players.find_duplicates(), vec![id(2)]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_has_stored_and_derived_counts() {
+        let json = serde_json::to_value(sample_old()).unwrap();
+        assert_eq!(json["winners_info_next_empty_index"], 2);
+        assert_eq!(json["active_player_count"], 3);
+        assert_eq!(json["winner_count"], 2);
+    }
+
+    #[test]
+    fn players_iter_skips_zero_and_unoccupied_slots() {
+        let mut old = sample_old();
+        // Occupied but zero
+        old.players.occupation_flags[0] |= 1 << (4 << 1);
+        // Unoccupied but non-zero, a stale id
+        old.players.players[5] = id(0x55);
+
+        let ids: Vec<Id> = old.players_iter().copied().collect();
+        assert_eq!(ids, [id(1), id(2), id(3)]);
+        assert_eq!(old.active_player_count(), 3);
+        assert_eq!(
+            NewRL::from_old(&old).active_player_count(),
+            old.active_player_count()
+        );
+    }
+
+    #[test]
+    fn size_constant_matches_the_struct_and_its_image() {
+        assert_eq!(OLD_RL_SIZE, size_of::<OldRL>());
+        assert_eq!(sample_old().to_bytes().len(), OLD_RL_SIZE);
+        assert_eq!(
+            DrawGuard::EXTENDED_OLD_SIZE,
+            size_of::<OldRL>() + size_of::<DrawGuard>()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reader_stops_one_byte_past_the_image() {
+        let mut image = sample_old().to_bytes();
+        let old = OldRL::from_reader(&mut std::io::Cursor::new(&image)).unwrap();
+        assert_eq!(old.to_bytes(), image);
+
+        image.resize(OLD_RL_SIZE + 1_000, 0);
+        let mut cursor = std::io::Cursor::new(&image);
+        assert!(matches!(
+            OldRL::from_reader(&mut cursor),
+            Err(ConvertError::SizeMismatch { actual, .. }) if actual == OLD_RL_SIZE + 1
+        ));
+        assert_eq!(cursor.position(), OLD_RL_SIZE as u64 + 1);
     }
 }
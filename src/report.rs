@@ -0,0 +1,179 @@
+//! Summary of what an `OldRL` → `NewRL` conversion carried over, defaulted or lost.
+
+use crate::new_rl::{NewRL, NextEpochData};
+use crate::old_rl::OldRL;
+use alloc::vec::Vec;
+
+/// Old-layout fields with no counterpart in `NewRL`.
+const DROPPED_FIELDS: [&str; 3] = [
+    "players.occupation_flags",
+    "players.population",
+    "players.mark_removal_counter",
+];
+
+/// Record of a single conversion, for audit trails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ConversionReport {
+    /// Non-empty winner slots copied into `NewRL::winners`.
+    pub migrated_winners: usize,
+    /// Active players copied into `NewRL::players`; an occupied slot
+    /// holding the zero address is not a player.
+    pub migrated_players: usize,
+    /// Non-zero ids in unoccupied hash set slots that were not copied.
+    pub skipped_stale_players: usize,
+    /// Whether the winners ring buffer had wrapped, so older winners were
+    /// already overwritten before the conversion.
+    pub history_wrapped: bool,
+    /// Migrated winners whose `day_of_week` was left at 0; the old layout
+    /// does not record when a winner was drawn.
+    pub day_of_week_defaults: usize,
+    /// New-only fields that are still zero in the result.
+    pub zeroed_fields: Vec<&'static str>,
+    /// Old fields that were not carried over.
+    pub dropped_fields: Vec<&'static str>,
+}
+
+impl ConversionReport {
+    /// Describes the conversion of `old` into `new`.
+    ///
+    /// `new` may already contain values set after the conversion (e.g. from
+    /// the command line); only fields that are still zero are listed as
+    /// zeroed.
+    pub fn new(old: &OldRL, new: &NewRL) -> Self {
+        let migrated_winners = old
+            .winners
+            .iter()
+            .filter(|w| !w.winner_address.is_zero())
+            .count();
+        let migrated_players = old.active_player_count();
+        let skipped_stale_players = old
+            .players
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(i, id)| !id.is_zero() && !old.players.is_occupied(*i))
+            .count();
+
+        let new_only_fields = [
+            (
+                "next_epoch_data",
+                new.next_epoch_data == NextEpochData::default(),
+            ),
+            ("player_counter", new.player_counter == 0),
+            ("last_draw_day", new.last_draw_day == 0),
+            ("last_draw_hour", new.last_draw_hour == 0),
+            ("last_draw_date_stamp", new.last_draw_date_stamp == 0),
+            ("schedule", new.schedule == 0),
+            ("draw_hour", new.draw_hour == 0),
+        ];
+
+        Self {
+            migrated_winners,
+            migrated_players,
+            skipped_stale_players,
+            history_wrapped: new.has_wrapped(),
+            day_of_week_defaults: migrated_winners,
+            zeroed_fields: new_only_fields
+                .iter()
+                .filter(|(_, zeroed)| *zeroed)
+                .map(|(name, _)| *name)
+                .collect(),
+            dropped_fields: DROPPED_FIELDS.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Id, RL_MAX_NUMBER_OF_PLAYERS, RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY};
+    use crate::convert_with_report;
+    use crate::new_rl::NewFieldOverrides;
+    use crate::old_rl::{PlayerHashSet, WinnerInfo};
+
+    fn id(byte: u8) -> Id {
+        Id { data: [byte; 32] }
+    }
+
+    /// Two players, one stale id in a freed slot and one occupied slot
+    /// holding the zero address.
+    fn sample_old() -> OldRL {
+        let mut players = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+        players[0] = id(1);
+        players[1] = id(2);
+        let mut old = OldRL {
+            players: PlayerHashSet::from_players(&players),
+            ..OldRL::default()
+        };
+        old.players.players[7] = id(7);
+        old.players.occupation_flags[0] |= 1 << (4 << 1);
+        old
+    }
+
+    #[test]
+    fn stale_players_are_skipped_and_counted() {
+        let (new_rl, report) = convert_with_report(&sample_old());
+
+        assert_eq!(report.migrated_players, 2);
+        assert_eq!(report.skipped_stale_players, 1);
+        assert_eq!(new_rl.active_player_count(), 2);
+        assert!(new_rl.players[7].is_zero());
+        assert_eq!(report.migrated_winners, 0);
+        assert!(!report.history_wrapped);
+        assert_eq!(report.dropped_fields, DROPPED_FIELDS);
+    }
+
+    #[test]
+    fn wrapped_history_is_reported() {
+        let mut old = sample_old();
+        for (i, winner) in old.winners.iter_mut().enumerate() {
+            *winner = WinnerInfo {
+                winner_address: id(9),
+                revenue: 100,
+                epoch: i as u16,
+                tick: 0,
+            };
+        }
+        old.winners_info_next_empty_index = RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY as u64 + 5;
+
+        let (_, report) = convert_with_report(&old);
+        assert!(report.history_wrapped);
+        assert_eq!(report.migrated_winners, RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY);
+        assert_eq!(
+            report.day_of_week_defaults,
+            RL_MAX_NUMBER_OF_WINNERS_IN_HISTORY
+        );
+    }
+
+    #[test]
+    fn overridden_fields_are_not_listed_as_zeroed() {
+        let old = sample_old();
+        let (mut new_rl, report) = convert_with_report(&old);
+        // player_counter is seeded from the migrated players
+        assert_eq!(
+            report.zeroed_fields,
+            [
+                "next_epoch_data",
+                "last_draw_day",
+                "last_draw_hour",
+                "last_draw_date_stamp",
+                "schedule",
+                "draw_hour",
+            ]
+        );
+
+        NewFieldOverrides {
+            schedule: Some(0b0000_0101),
+            draw_hour: Some(20),
+            next_price: Some(2_000),
+            next_schedule: None,
+        }
+        .apply_to(&mut new_rl);
+        let report = ConversionReport::new(&old, &new_rl);
+        assert_eq!(
+            report.zeroed_fields,
+            ["last_draw_day", "last_draw_hour", "last_draw_date_stamp"]
+        );
+    }
+}
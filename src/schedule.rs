@@ -0,0 +1,349 @@
+//! Typed view of the weekday bitmask used by `NewRL::schedule`.
+
+use crate::new_rl::SCHEDULE_MASK;
+use core::fmt::{self, Display, Formatter};
+
+/// Day of the week in the contract's convention, where the week starts on
+/// Wednesday (the day epochs switch). The discriminant is the schedule bit
+/// index and matches `common::day_of_week`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Weekday {
+    /// Bit 0. Scheduled Wednesday draws also follow the "Two-Wednesdays rule".
+    Wednesday = 0,
+    Thursday = 1,
+    Friday = 2,
+    Saturday = 3,
+    Sunday = 4,
+    Monday = 5,
+    Tuesday = 6,
+}
+
+impl Weekday {
+    /// All days in bit order, Wednesday first.
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+        Weekday::Monday,
+        Weekday::Tuesday,
+    ];
+
+    /// Day for a bit index / `day_of_week` value, `None` above 6.
+    pub fn from_index(index: u8) -> Option<Weekday> {
+        Self::ALL.get(usize::from(index)).copied()
+    }
+
+    /// Single-bit mask of this day in a schedule.
+    pub fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// Three-letter upper-case name, e.g. `WED`.
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Weekday::Wednesday => "WED",
+            Weekday::Thursday => "THU",
+            Weekday::Friday => "FRI",
+            Weekday::Saturday => "SAT",
+            Weekday::Sunday => "SUN",
+            Weekday::Monday => "MON",
+            Weekday::Tuesday => "TUE",
+        }
+    }
+}
+
+impl Display for Weekday {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.short_name())
+    }
+}
+
+/// Set of weekdays on which a draw may run.
+///
+/// Bit 0 = Wednesday, 1 = Thursday, ..., 6 = Tuesday. A set Wednesday bit is
+/// further restricted by the "Two-Wednesdays rule". Bits above bit 6 are
+/// kept as-is so a raw value round-trips, but never correspond to a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Schedule(u8);
+
+impl Schedule {
+    pub fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(self, day: Weekday) -> bool {
+        self.0 & day.bit() != 0
+    }
+
+    pub fn insert(&mut self, day: Weekday) {
+        self.0 |= day.bit();
+    }
+
+    pub fn remove(&mut self, day: Weekday) {
+        self.0 &= !day.bit();
+    }
+
+    /// Whether only weekday bits are set (nothing above bit 6).
+    pub fn is_valid(self) -> bool {
+        self.0 & !SCHEDULE_MASK == 0
+    }
+
+    /// Whether no weekday bit is set.
+    pub fn is_empty(self) -> bool {
+        self.0 & SCHEDULE_MASK == 0
+    }
+
+    /// Enabled days in bit order.
+    pub fn days(self) -> impl Iterator<Item = Weekday> {
+        Weekday::ALL
+            .into_iter()
+            .filter(move |day| self.contains(*day))
+    }
+}
+
+impl From<u8> for Schedule {
+    fn from(bits: u8) -> Self {
+        Self(bits)
+    }
+}
+
+impl From<Schedule> for u8 {
+    fn from(schedule: Schedule) -> Self {
+        schedule.0
+    }
+}
+
+impl Display for Schedule {
+    /// Enabled days by name, e.g. `WED, SAT`, or `none`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("none");
+        }
+        for (i, day) in self.days().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", day)?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies `schedule` and the "Two-Wednesdays rule" to a candidate draw day.
+///
+/// A day other than Wednesday is allowed exactly when its bit is set.
+/// Wednesday is where epochs switch, so Wednesdays come in pairs around a
+/// draw period: the first one is never a draw day, and a Wednesday draw is
+/// allowed only on the second Wednesday (`is_second_wednesday`) and only
+/// if bit 0 is set. Which Wednesday is the second is decided by the caller;
+/// the stored state does not record it. The draw hour and the once-per-day
+/// guard are checked separately.
+pub fn is_draw_allowed(schedule: u8, weekday: Weekday, is_second_wednesday: bool) -> bool {
+    let scheduled = Schedule::from_bits(schedule).contains(weekday);
+    match weekday {
+        Weekday::Wednesday => scheduled && is_second_wednesday,
+        _ => scheduled,
+    }
+}
+
+/// Draw time found by `upcoming_draws`.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UpcomingDraw {
+    /// Start of the draw hour, UTC.
+    pub at: chrono::DateTime<chrono::Utc>,
+    pub weekday: Weekday,
+    /// A Wednesday that only draws if it is the second Wednesday, which
+    /// could not be decided because no anchor was given.
+    pub needs_second_wednesday: bool,
+}
+
+/// The next `count` draw times at or after `from`.
+///
+/// A draw runs at `draw_hour`:00 UTC on every scheduled day, so a day whose
+/// draw hour has already passed is skipped. Wednesdays follow the
+/// "Two-Wednesdays rule" (see `is_draw_allowed`): with `second_wednesday`,
+/// the date of any second Wednesday, the Wednesdays an even number of weeks
+/// away are the second ones and the others are skipped; without it every
+/// scheduled Wednesday is listed with `needs_second_wednesday` set.
+///
+/// The search stops after `count` draws or once 14 days in a row had no
+/// draw, so an empty schedule yields nothing. A Wednesday-only schedule has
+/// draws 14 days apart, and the day of `from` may already be past its draw
+/// hour, so 14 idle days still continue to a 15th.
+#[cfg(feature = "std")]
+pub fn upcoming_draws(
+    schedule: u8,
+    draw_hour: u8,
+    from: chrono::DateTime<chrono::Utc>,
+    second_wednesday: Option<chrono::NaiveDate>,
+    count: usize,
+) -> Vec<UpcomingDraw> {
+    use chrono::{Datelike, Days};
+
+    let mut draws = Vec::with_capacity(count);
+    let mut day = from.date_naive();
+    let mut idle_days = 0;
+    while draws.len() < count && idle_days <= 14 {
+        // chrono counts from Monday, the contract from Wednesday
+        let index = (day.weekday().num_days_from_monday() + 5) % 7;
+        let weekday = Weekday::from_index(index as u8).expect("index below 7");
+        let second = second_wednesday
+            .map(|anchor| (day - anchor).num_weeks().rem_euclid(2) == 0)
+            .unwrap_or(true);
+        let at = day
+            .and_hms_opt(u32::from(draw_hour), 0, 0)
+            .map(|at| at.and_utc())
+            .filter(|at| *at >= from);
+
+        match at {
+            Some(at) if is_draw_allowed(schedule, weekday, second) => {
+                draws.push(UpcomingDraw {
+                    at,
+                    weekday,
+                    needs_second_wednesday: weekday == Weekday::Wednesday
+                        && second_wednesday.is_none(),
+                });
+                idle_days = 0;
+            }
+            _ => idle_days += 1,
+        }
+        day = day + Days::new(1);
+    }
+    draws
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn weekday_bits_start_on_wednesday() {
+        let bits: Vec<u8> = Weekday::ALL.iter().map(|day| day.bit()).collect();
+        assert_eq!(bits, [1, 2, 4, 8, 16, 32, 64]);
+        assert_eq!(Weekday::from_index(0), Some(Weekday::Wednesday));
+        assert_eq!(Weekday::from_index(6), Some(Weekday::Tuesday));
+        assert_eq!(Weekday::from_index(7), None);
+    }
+
+    #[test]
+    fn schedule_lists_its_days_in_bit_order() {
+        let schedule = Schedule::from_bits(0b0100_1001);
+        let days: Vec<Weekday> = schedule.days().collect();
+        assert_eq!(
+            days,
+            [Weekday::Wednesday, Weekday::Saturday, Weekday::Tuesday]
+        );
+        assert_eq!(schedule.to_string(), "WED, SAT, TUE");
+        assert_eq!(Schedule::default().to_string(), "none");
+    }
+
+    #[test]
+    fn schedule_insert_and_remove_round_trip_the_bits() {
+        let mut schedule = Schedule::default();
+        schedule.insert(Weekday::Friday);
+        schedule.insert(Weekday::Monday);
+        assert_eq!(u8::from(schedule), 0b0010_0100);
+        schedule.remove(Weekday::Friday);
+        assert!(!schedule.contains(Weekday::Friday));
+        assert!(schedule.contains(Weekday::Monday));
+        assert!(Schedule::from_bits(0x80).is_empty());
+        assert!(!Schedule::from_bits(0x80).is_valid());
+    }
+
+    #[test]
+    fn first_wednesday_is_denied() {
+        let schedule = Weekday::Wednesday.bit() | Weekday::Saturday.bit();
+        assert!(!is_draw_allowed(schedule, Weekday::Wednesday, false));
+    }
+
+    #[test]
+    fn second_wednesday_is_allowed_when_scheduled() {
+        let schedule = Weekday::Wednesday.bit() | Weekday::Saturday.bit();
+        assert!(is_draw_allowed(schedule, Weekday::Wednesday, true));
+        assert!(!is_draw_allowed(
+            Weekday::Saturday.bit(),
+            Weekday::Wednesday,
+            true
+        ));
+    }
+
+    #[test]
+    fn other_days_follow_their_bit() {
+        let schedule = Weekday::Wednesday.bit() | Weekday::Saturday.bit();
+        assert!(is_draw_allowed(schedule, Weekday::Saturday, false));
+        assert!(is_draw_allowed(schedule, Weekday::Saturday, true));
+        assert!(!is_draw_allowed(schedule, Weekday::Friday, true));
+    }
+
+    #[cfg(feature = "std")]
+    mod upcoming {
+        use super::*;
+        use chrono::{NaiveDate, TimeZone, Utc};
+
+        fn at(day: u32, hour: u32) -> chrono::DateTime<Utc> {
+            Utc.with_ymd_and_hms(2025, 1, day, hour, 0, 0).unwrap()
+        }
+
+        #[test]
+        fn simple_schedule_from_fixed_now() {
+            // 2025-01-01 is a Wednesday; Thursday and Saturday at 12:00
+            let schedule = Weekday::Thursday.bit() | Weekday::Saturday.bit();
+            let draws = upcoming_draws(schedule, 12, at(1, 13), None, 4);
+            let times: Vec<_> = draws.iter().map(|draw| draw.at).collect();
+            assert_eq!(times, [at(2, 12), at(4, 12), at(9, 12), at(11, 12)]);
+            assert_eq!(draws[0].weekday, Weekday::Thursday);
+            assert_eq!(draws[1].weekday, Weekday::Saturday);
+            assert!(draws.iter().all(|draw| !draw.needs_second_wednesday));
+        }
+
+        #[test]
+        fn draw_hour_gates_the_first_day() {
+            let schedule = Weekday::Thursday.bit();
+            assert_eq!(
+                upcoming_draws(schedule, 12, at(2, 11), None, 1)[0].at,
+                at(2, 12)
+            );
+            assert_eq!(
+                upcoming_draws(schedule, 12, at(2, 12), None, 1)[0].at,
+                at(2, 12)
+            );
+            assert_eq!(
+                upcoming_draws(schedule, 12, at(2, 13), None, 1)[0].at,
+                at(9, 12)
+            );
+        }
+
+        #[test]
+        fn wednesday_only_skips_to_the_next_second_wednesday() {
+            // Just past the draw of a second Wednesday, the next draw is 14 days away
+            let anchor = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+            let draws = upcoming_draws(Weekday::Wednesday.bit(), 12, at(1, 13), Some(anchor), 2);
+            let times: Vec<_> = draws.iter().map(|draw| draw.at).collect();
+            assert_eq!(times, [at(15, 12), at(29, 12)]);
+            assert!(draws.iter().all(|draw| draw.weekday == Weekday::Wednesday));
+        }
+
+        #[test]
+        fn wednesdays_without_anchor_are_conditional() {
+            let draws = upcoming_draws(Weekday::Wednesday.bit(), 12, at(1, 0), None, 2);
+            let times: Vec<_> = draws.iter().map(|draw| draw.at).collect();
+            assert_eq!(times, [at(1, 12), at(8, 12)]);
+            assert!(draws.iter().all(|draw| draw.needs_second_wednesday));
+        }
+
+        #[test]
+        fn empty_schedule_has_no_draws() {
+            assert!(upcoming_draws(0, 12, at(1, 0), None, 5).is_empty());
+        }
+    }
+}
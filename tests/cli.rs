@@ -0,0 +1,215 @@
+//! Runs the `rlconverter` binary end to end on states written to a
+//! temporary directory.
+
+use rlconverter::common::{EState, Id, RL_MAX_NUMBER_OF_PLAYERS};
+use rlconverter::old_rl::{OldRL, PlayerHashSet};
+use rlconverter::write_old_rl_to_bytes;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// An empty scratch directory unique to `name`.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rlconverter-cli-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A selling state with a 10/20/50/20 split and one player.
+fn sample_old() -> OldRL {
+    let mut players = [Id::zero(); RL_MAX_NUMBER_OF_PLAYERS];
+    players[0] = Id { data: [3; 32] };
+    OldRL {
+        team_address: Id { data: [1; 32] },
+        owner_address: Id { data: [2; 32] },
+        team_fee_percent: 10,
+        distribution_fee_percent: 20,
+        winner_fee_percent: 50,
+        burn_percent: 20,
+        ticket_price: 1_000,
+        players: PlayerHashSet::from_players(&players),
+        current_state: EState::Selling,
+        ..OldRL::default()
+    }
+}
+
+fn rlconverter(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rlconverter"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn quiet_conversion_prints_nothing_on_success() {
+    let dir = temp_dir("quiet");
+    let input = dir.join("contract0016.185");
+    let output = dir.join("converted.185");
+    std::fs::write(&input, write_old_rl_to_bytes(&sample_old())).unwrap();
+
+    let result = rlconverter(&["--quiet", input.to_str().unwrap(), output.to_str().unwrap()]);
+    assert!(result.status.success(), "{:?}", result);
+    assert!(result.stdout.is_empty());
+    assert!(result.stderr.is_empty());
+    assert!(output.exists());
+}
+
+#[test]
+fn quiet_conversion_still_fails_on_a_bad_input() {
+    let dir = temp_dir("quiet-error");
+    let input = dir.join("contract0016.185");
+    std::fs::write(&input, [0u8; 100]).unwrap();
+
+    let result = rlconverter(&[
+        "--quiet",
+        input.to_str().unwrap(),
+        dir.join("converted.185").to_str().unwrap(),
+    ]);
+    assert!(!result.status.success());
+    assert!(result.stdout.is_empty());
+    assert!(!result.stderr.is_empty());
+}
+
+/// Exit code of converting `image` written as the input file; `None` for a
+/// missing input.
+fn conversion_exit_code(name: &str, image: Option<Vec<u8>>) -> Option<i32> {
+    let dir = temp_dir(name);
+    let input = dir.join("contract0016.185");
+    if let Some(image) = image {
+        std::fs::write(&input, image).unwrap();
+    }
+    let result = rlconverter(&[
+        "--quiet",
+        input.to_str().unwrap(),
+        dir.join("converted.185").to_str().unwrap(),
+    ]);
+    result.status.code()
+}
+
+#[test]
+fn missing_input_exits_with_2() {
+    assert_eq!(conversion_exit_code("exit-io", None), Some(2));
+}
+
+#[test]
+fn wrong_size_exits_with_3() {
+    assert_eq!(
+        conversion_exit_code("exit-size", Some(vec![0; 100])),
+        Some(3)
+    );
+}
+
+#[test]
+fn invalid_state_byte_exits_with_4() {
+    let mut image = write_old_rl_to_bytes(&sample_old());
+    image[std::mem::offset_of!(OldRL, current_state)] = 7;
+    assert_eq!(conversion_exit_code("exit-data", Some(image)), Some(4));
+}
+
+#[test]
+fn invalid_fee_split_exits_with_5() {
+    let old = OldRL {
+        winner_fee_percent: 40,
+        ..sample_old()
+    };
+    assert_eq!(
+        conversion_exit_code("exit-validation", Some(write_old_rl_to_bytes(&old))),
+        Some(5)
+    );
+}
+
+#[test]
+fn owner_equal_to_team_fails_only_under_strict() {
+    let old = OldRL {
+        owner_address: Id { data: [1; 32] },
+        ..sample_old()
+    };
+    let dir = temp_dir("owner-is-team");
+    let input = dir.join("contract0016.185");
+    std::fs::write(&input, write_old_rl_to_bytes(&old)).unwrap();
+
+    let strict = rlconverter(&[
+        "--quiet",
+        "--strict",
+        input.to_str().unwrap(),
+        dir.join("strict.185").to_str().unwrap(),
+    ]);
+    assert_eq!(strict.status.code(), Some(5));
+
+    let lenient = rlconverter(&[
+        input.to_str().unwrap(),
+        dir.join("lenient.185").to_str().unwrap(),
+    ]);
+    assert!(lenient.status.success(), "{:?}", lenient);
+    assert!(String::from_utf8_lossy(&lenient.stderr).contains("owner"));
+}
+
+#[test]
+fn quiet_batch_prints_no_progress() {
+    let dir = temp_dir("batch");
+    let inputs = dir.join("inputs");
+    let outputs = dir.join("outputs");
+    std::fs::create_dir_all(&inputs).unwrap();
+    std::fs::create_dir_all(&outputs).unwrap();
+    for name in ["contract0016.185", "contract0016.186"] {
+        std::fs::write(inputs.join(name), write_old_rl_to_bytes(&sample_old())).unwrap();
+    }
+
+    let args = [
+        inputs.to_str().unwrap(),
+        "--output-dir",
+        outputs.to_str().unwrap(),
+    ];
+    let loud = rlconverter(&args);
+    assert!(loud.status.success(), "{:?}", loud);
+    assert!(String::from_utf8_lossy(&loud.stdout).contains("[2/2] contract0016.186"));
+
+    std::fs::remove_dir_all(&outputs).unwrap();
+    std::fs::create_dir_all(&outputs).unwrap();
+    let quiet = rlconverter(&[&["--quiet"], &args[..]].concat());
+    assert!(quiet.status.success(), "{:?}", quiet);
+    assert!(quiet.stdout.is_empty());
+    assert!(quiet.stderr.is_empty());
+    assert!(outputs.join("contract0016_new.185").exists());
+    assert!(outputs.join("contract0016_new.186").exists());
+}
+
+#[test]
+fn colliding_template_outputs_are_refused() {
+    let dir = temp_dir("template-collision");
+    for name in ["contract0016.185", "contract0016.186"] {
+        std::fs::write(dir.join(name), write_old_rl_to_bytes(&sample_old())).unwrap();
+    }
+
+    let result = rlconverter(&[
+        "--quiet",
+        "--output-template",
+        "{dir}/migrated/{stem}.new",
+        dir.to_str().unwrap(),
+    ]);
+    assert_eq!(result.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&result.stderr).contains("would both be written to"));
+    assert!(!dir.join("migrated").exists());
+}
+
+#[test]
+fn template_mapping_inputs_onto_themselves_is_refused() {
+    let dir = temp_dir("template-in-place");
+    let input = dir.join("contract0016.185");
+    let image = write_old_rl_to_bytes(&sample_old());
+    std::fs::write(&input, &image).unwrap();
+
+    for template in ["{dir}/{stem}.{ext}", "{dir}/./{stem}.{ext}"] {
+        let result = rlconverter(&[
+            "--quiet",
+            "--output-template",
+            template,
+            dir.to_str().unwrap(),
+        ]);
+        assert_eq!(result.status.code(), Some(1), "{:?}", result);
+        assert!(
+            String::from_utf8_lossy(&result.stderr).contains("refusing to overwrite the source")
+        );
+        assert_eq!(std::fs::read(&input).unwrap(), image);
+    }
+}